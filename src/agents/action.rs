@@ -1,5 +1,6 @@
-use crate::core::{CubeElement, CubeLoader, CubeModel};
-use enum_iterator::Sequence;
+use crate::core::{CubeModel, Face, PackedCube, Symmetry};
+use enum_iterator::{all, Sequence};
+use std::{fmt::Display, sync::OnceLock};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence)]
 pub enum Actions {
@@ -15,6 +16,375 @@ pub enum Actions {
     RC,
     L,
     LC,
+    // half turns
+    U2,
+    D2,
+    F2,
+    B2,
+    R2,
+    L2,
+    // slices
+    M,
+    MC,
+    M2,
+    E,
+    EC,
+    E2,
+    S,
+    SC,
+    S2,
+    // wide turns
+    Uw,
+    UwC,
+    Uw2,
+    Dw,
+    DwC,
+    Dw2,
+    Fw,
+    FwC,
+    Fw2,
+    Bw,
+    BwC,
+    Bw2,
+    Rw,
+    RwC,
+    Rw2,
+    Lw,
+    LwC,
+    Lw2,
+    // whole cube rotations
+    X,
+    XC,
+    X2,
+    Y,
+    YC,
+    Y2,
+    Z,
+    ZC,
+    Z2,
+}
+
+/// The layers an action moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layers {
+    Face,
+    Slice,
+    Wide,
+    Cube,
+}
+
+impl Actions {
+    /// The twelve quarter turns of the outer faces.
+    pub const QUARTER_TURNS: [Actions; 12] = [
+        Actions::U,
+        Actions::UC,
+        Actions::D,
+        Actions::DC,
+        Actions::F,
+        Actions::FC,
+        Actions::B,
+        Actions::BC,
+        Actions::R,
+        Actions::RC,
+        Actions::L,
+        Actions::LC,
+    ];
+
+    /// Quarter and half turns of the outer faces, the moves counted by the half turn metric.
+    pub const FACE_TURNS: [Actions; 18] = [
+        Actions::U,
+        Actions::UC,
+        Actions::U2,
+        Actions::D,
+        Actions::DC,
+        Actions::D2,
+        Actions::F,
+        Actions::FC,
+        Actions::F2,
+        Actions::B,
+        Actions::BC,
+        Actions::B2,
+        Actions::R,
+        Actions::RC,
+        Actions::R2,
+        Actions::L,
+        Actions::LC,
+        Actions::L2,
+    ];
+
+    /// The outer face turned by a face turn, None for slices, wide turns and rotations.
+    pub fn face(self) -> Option<Face> {
+        match self {
+            Actions::U | Actions::UC | Actions::U2 => Some(Face::Up),
+            Actions::D | Actions::DC | Actions::D2 => Some(Face::Down),
+            Actions::F | Actions::FC | Actions::F2 => Some(Face::Front),
+            Actions::B | Actions::BC | Actions::B2 => Some(Face::Back),
+            Actions::R | Actions::RC | Actions::R2 => Some(Face::Right),
+            Actions::L | Actions::LC | Actions::L2 => Some(Face::Left),
+            _ => None,
+        }
+    }
+
+    /// Number of clockwise quarter turns the action amounts to: 1, 2 for half turns or 3 for
+    /// counter clockwise turns.
+    pub fn quarter_turns(self) -> u8 {
+        match self.notation().chars().last() {
+            Some('2') => 2,
+            Some('\'') => 3,
+            _ => 1,
+        }
+    }
+
+    /// The face turn of `face` by the given clockwise quarter turns, None if they cancel out.
+    pub fn face_turn(face: Face, quarter_turns: u8) -> Option<Self> {
+        let (clockwise, half_turn, counter_clockwise) = match face {
+            Face::Up => (Actions::U, Actions::U2, Actions::UC),
+            Face::Down => (Actions::D, Actions::D2, Actions::DC),
+            Face::Front => (Actions::F, Actions::F2, Actions::FC),
+            Face::Back => (Actions::B, Actions::B2, Actions::BC),
+            Face::Right => (Actions::R, Actions::R2, Actions::RC),
+            Face::Left => (Actions::L, Actions::L2, Actions::LC),
+        };
+        match quarter_turns % 4 {
+            1 => Some(clockwise),
+            2 => Some(half_turn),
+            3 => Some(counter_clockwise),
+            _ => None,
+        }
+    }
+
+    /// The layers moved by the action and the face its turn is clockwise from: the turned face
+    /// for face and wide turns, L for M, D for E, F for S, R for x, U for y and F for z.
+    fn layers(self) -> (Layers, Face) {
+        let face = match self.notation().chars().next().unwrap().to_ascii_uppercase() {
+            'U' | 'Y' => Face::Up,
+            'D' | 'E' => Face::Down,
+            'F' | 'S' | 'Z' => Face::Front,
+            'B' => Face::Back,
+            'R' | 'X' => Face::Right,
+            _ => Face::Left,
+        };
+        let layers = match self.notation().chars().next().unwrap() {
+            'M' | 'E' | 'S' => Layers::Slice,
+            'x' | 'y' | 'z' => Layers::Cube,
+            c if c.is_lowercase() => Layers::Wide,
+            _ => Layers::Face,
+        };
+        (layers, face)
+    }
+
+    /// The action moving `layers` by the given clockwise quarter turns from `face`, None if they
+    /// cancel out or if no action of these layers is named after that face.
+    fn from_layers(layers: Layers, face: Face, quarter_turns: u8) -> Option<Self> {
+        all::<Actions>()
+            .find(|action| action.layers() == (layers, face) && action.quarter_turns() == quarter_turns % 4)
+    }
+
+    /// The axis the action turns around: 0 for the R and L axis, 1 for U and D and 2 for F and B.
+    fn axis(self) -> usize {
+        match self.layers().1.normal() {
+            (0, 0, _) => 2,
+            (0, _, _) => 1,
+            _ => 0,
+        }
+    }
+
+    /// True when both actions turn around the same axis, in which case they commute.
+    pub fn commutes_with(self, other: Actions) -> bool {
+        self.axis() == other.axis()
+    }
+
+    /// True when both actions move the same layers, e.g. R and R2 or M and M'.
+    pub fn same_layers(self, other: Actions) -> bool {
+        self.layers() == other.layers()
+    }
+
+    /// The action moving the same layers by the given clockwise quarter turns more, None if the
+    /// turns cancel out, e.g. R2 for R turned once more.
+    pub fn turned(self, quarter_turns: u8) -> Option<Self> {
+        let (layers, face) = self.layers();
+        Self::from_layers(layers, face, self.quarter_turns() + quarter_turns)
+    }
+
+    /// Key ordering commuting actions in canonical sequences: face turns first, U before D,
+    /// F before B and R before L.
+    pub(super) fn canonical_order(self) -> (u8, u8) {
+        let (layers, face) = self.layers();
+        (layers as u8, face as u8)
+    }
+
+    /// True when the action may follow `previous` in a canonical sequence of face turns: a face
+    /// is never turned twice in a row, and turns of opposite faces, which commute, only come in
+    /// the order of `canonical_order`. Searches only generating such sequences never reach a
+    /// state twice through the same moves in another order, or through moves that merge.
+    pub fn follows_canonically(self, previous: Option<Actions>) -> bool {
+        match (previous, self.face()) {
+            (Some(previous), Some(_)) if previous.face().is_some() => {
+                !self.same_layers(previous)
+                    && (!self.commutes_with(previous) || self.canonical_order() > previous.canonical_order())
+            }
+            _ => true,
+        }
+    }
+
+    /// The action conjugated by a symmetry, moving the layers the symmetry maps the action's
+    /// layers to: e.g. R becomes L' through `Symmetry::MIRROR`. Applying the conjugated
+    /// actions to a cube conjugated by the symmetry gives the conjugate of the result.
+    pub fn conjugate(self, symmetry: Symmetry) -> Self {
+        let (layers, face) = self.layers();
+        let face = symmetry.face(face);
+        let mut quarter_turns = self.quarter_turns();
+        if symmetry.is_mirror() {
+            quarter_turns = 4 - quarter_turns;
+        }
+        // slices and rotations are named after one face of their axis only
+        Self::from_layers(layers, face, quarter_turns)
+            .or_else(|| Self::from_layers(layers, face.opposite(), 4 - quarter_turns))
+            .unwrap()
+    }
+
+    /// The action applied to the solved cube, in packed form. This is the move table used to
+    /// apply the action to packed cubes, built from the model on first use.
+    pub fn packed(self) -> &'static PackedCube {
+        static MOVES: OnceLock<Vec<PackedCube>> = OnceLock::new();
+        let moves = MOVES.get_or_init(|| {
+            all::<Actions>()
+                .map(|action| {
+                    let mut model = CubeModel::solved();
+                    model.apply_action(action);
+                    PackedCube::try_from(&model).unwrap()
+                })
+                .collect()
+        });
+        &moves[self as usize]
+    }
+
+    /// The action that undoes this one.
+    pub fn inverse(self) -> Self {
+        match self {
+            Actions::U => Actions::UC,
+            Actions::UC => Actions::U,
+            Actions::U2 => Actions::U2,
+            Actions::D => Actions::DC,
+            Actions::DC => Actions::D,
+            Actions::D2 => Actions::D2,
+            Actions::F => Actions::FC,
+            Actions::FC => Actions::F,
+            Actions::F2 => Actions::F2,
+            Actions::B => Actions::BC,
+            Actions::BC => Actions::B,
+            Actions::B2 => Actions::B2,
+            Actions::R => Actions::RC,
+            Actions::RC => Actions::R,
+            Actions::R2 => Actions::R2,
+            Actions::L => Actions::LC,
+            Actions::LC => Actions::L,
+            Actions::L2 => Actions::L2,
+            Actions::M => Actions::MC,
+            Actions::MC => Actions::M,
+            Actions::M2 => Actions::M2,
+            Actions::E => Actions::EC,
+            Actions::EC => Actions::E,
+            Actions::E2 => Actions::E2,
+            Actions::S => Actions::SC,
+            Actions::SC => Actions::S,
+            Actions::S2 => Actions::S2,
+            Actions::Uw => Actions::UwC,
+            Actions::UwC => Actions::Uw,
+            Actions::Uw2 => Actions::Uw2,
+            Actions::Dw => Actions::DwC,
+            Actions::DwC => Actions::Dw,
+            Actions::Dw2 => Actions::Dw2,
+            Actions::Fw => Actions::FwC,
+            Actions::FwC => Actions::Fw,
+            Actions::Fw2 => Actions::Fw2,
+            Actions::Bw => Actions::BwC,
+            Actions::BwC => Actions::Bw,
+            Actions::Bw2 => Actions::Bw2,
+            Actions::Rw => Actions::RwC,
+            Actions::RwC => Actions::Rw,
+            Actions::Rw2 => Actions::Rw2,
+            Actions::Lw => Actions::LwC,
+            Actions::LwC => Actions::Lw,
+            Actions::Lw2 => Actions::Lw2,
+            Actions::X => Actions::XC,
+            Actions::XC => Actions::X,
+            Actions::X2 => Actions::X2,
+            Actions::Y => Actions::YC,
+            Actions::YC => Actions::Y,
+            Actions::Y2 => Actions::Y2,
+            Actions::Z => Actions::ZC,
+            Actions::ZC => Actions::Z,
+            Actions::Z2 => Actions::Z2,
+        }
+    }
+
+    /// The action written in Singmaster notation, e.g. `U'` for `Actions::UC`.
+    /// Wide turns and rotations are written in lowercase, e.g. `r` and `x`.
+    pub fn notation(self) -> &'static str {
+        match self {
+            Actions::U => "U",
+            Actions::UC => "U'",
+            Actions::U2 => "U2",
+            Actions::D => "D",
+            Actions::DC => "D'",
+            Actions::D2 => "D2",
+            Actions::F => "F",
+            Actions::FC => "F'",
+            Actions::F2 => "F2",
+            Actions::B => "B",
+            Actions::BC => "B'",
+            Actions::B2 => "B2",
+            Actions::R => "R",
+            Actions::RC => "R'",
+            Actions::R2 => "R2",
+            Actions::L => "L",
+            Actions::LC => "L'",
+            Actions::L2 => "L2",
+            Actions::M => "M",
+            Actions::MC => "M'",
+            Actions::M2 => "M2",
+            Actions::E => "E",
+            Actions::EC => "E'",
+            Actions::E2 => "E2",
+            Actions::S => "S",
+            Actions::SC => "S'",
+            Actions::S2 => "S2",
+            Actions::Uw => "u",
+            Actions::UwC => "u'",
+            Actions::Uw2 => "u2",
+            Actions::Dw => "d",
+            Actions::DwC => "d'",
+            Actions::Dw2 => "d2",
+            Actions::Fw => "f",
+            Actions::FwC => "f'",
+            Actions::Fw2 => "f2",
+            Actions::Bw => "b",
+            Actions::BwC => "b'",
+            Actions::Bw2 => "b2",
+            Actions::Rw => "r",
+            Actions::RwC => "r'",
+            Actions::Rw2 => "r2",
+            Actions::Lw => "l",
+            Actions::LwC => "l'",
+            Actions::Lw2 => "l2",
+            Actions::X => "x",
+            Actions::XC => "x'",
+            Actions::X2 => "x2",
+            Actions::Y => "y",
+            Actions::YC => "y'",
+            Actions::Y2 => "y2",
+            Actions::Z => "z",
+            Actions::ZC => "z'",
+            Actions::Z2 => "z2",
+        }
+    }
+}
+
+impl Display for Actions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.notation())
+    }
 }
 
 pub trait Actionable {
@@ -26,16 +396,98 @@ impl Actionable for CubeModel {
         match action {
             Actions::U => self.up_clockwise(),
             Actions::UC => self.up_counter_clockwise(),
+            Actions::U2 => self.up_half_turn(),
             Actions::D => self.down_clockwise(),
             Actions::DC => self.down_counter_clockwise(),
+            Actions::D2 => self.down_half_turn(),
             Actions::F => self.front_clockwise(),
             Actions::FC => self.front_counter_clockwise(),
+            Actions::F2 => self.front_half_turn(),
             Actions::B => self.back_clockwise(),
             Actions::BC => self.back_counter_clockwise(),
+            Actions::B2 => self.back_half_turn(),
             Actions::R => self.right_clockwise(),
             Actions::RC => self.right_counter_clockwise(),
+            Actions::R2 => self.right_half_turn(),
             Actions::L => self.left_clockwise(),
             Actions::LC => self.left_counter_clockwise(),
+            Actions::L2 => self.left_half_turn(),
+            Actions::M => self.middle_clockwise(),
+            Actions::MC => self.middle_counter_clockwise(),
+            Actions::M2 => self.middle_half_turn(),
+            Actions::E => self.equator_clockwise(),
+            Actions::EC => self.equator_counter_clockwise(),
+            Actions::E2 => self.equator_half_turn(),
+            Actions::S => self.standing_clockwise(),
+            Actions::SC => self.standing_counter_clockwise(),
+            Actions::S2 => self.standing_half_turn(),
+            Actions::Uw => self.up_wide_clockwise(),
+            Actions::UwC => self.up_wide_counter_clockwise(),
+            Actions::Uw2 => self.up_wide_half_turn(),
+            Actions::Dw => self.down_wide_clockwise(),
+            Actions::DwC => self.down_wide_counter_clockwise(),
+            Actions::Dw2 => self.down_wide_half_turn(),
+            Actions::Fw => self.front_wide_clockwise(),
+            Actions::FwC => self.front_wide_counter_clockwise(),
+            Actions::Fw2 => self.front_wide_half_turn(),
+            Actions::Bw => self.back_wide_clockwise(),
+            Actions::BwC => self.back_wide_counter_clockwise(),
+            Actions::Bw2 => self.back_wide_half_turn(),
+            Actions::Rw => self.right_wide_clockwise(),
+            Actions::RwC => self.right_wide_counter_clockwise(),
+            Actions::Rw2 => self.right_wide_half_turn(),
+            Actions::Lw => self.left_wide_clockwise(),
+            Actions::LwC => self.left_wide_counter_clockwise(),
+            Actions::Lw2 => self.left_wide_half_turn(),
+            Actions::X => self.rotation_x_clockwise(),
+            Actions::XC => self.rotation_x_counter_clockwise(),
+            Actions::X2 => self.rotation_x_half_turn(),
+            Actions::Y => self.rotation_y_clockwise(),
+            Actions::YC => self.rotation_y_counter_clockwise(),
+            Actions::Y2 => self.rotation_y_half_turn(),
+            Actions::Z => self.rotation_z_clockwise(),
+            Actions::ZC => self.rotation_z_counter_clockwise(),
+            Actions::Z2 => self.rotation_z_half_turn(),
+        }
+    }
+}
+
+impl Actionable for PackedCube {
+    fn apply_action(&mut self, action: Actions) {
+        *self = self.multiply(action.packed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_actions_match_the_model() {
+        let mut model = CubeModel::solved();
+        let mut cube = PackedCube::SOLVED;
+        for action in all::<Actions>() {
+            model.apply_action(action);
+            cube.apply_action(action);
+            assert_eq!(CubeModel::from(cube), model, "{}", action);
+        }
+    }
+
+    #[test]
+    fn conjugated_actions_match_conjugated_models() {
+        assert_eq!(Actions::R.conjugate(Symmetry::MIRROR), Actions::LC);
+        assert_eq!(Actions::M2.conjugate(Symmetry::MIRROR), Actions::M2);
+        assert_eq!(Actions::X.conjugate(Symmetry::MIRROR), Actions::X);
+        for symmetry in Symmetry::all() {
+            for action in all::<Actions>() {
+                let mut model = CubeModel::solved();
+                model.apply_action(Actions::R);
+                model.apply_action(Actions::F);
+                let mut conjugated = symmetry.conjugate(&model);
+                model.apply_action(action);
+                conjugated.apply_action(action.conjugate(symmetry));
+                assert_eq!(symmetry.conjugate(&model), conjugated, "{} {:?}", action, symmetry);
+            }
         }
     }
 }
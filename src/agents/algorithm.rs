@@ -0,0 +1,340 @@
+use super::action::{Actionable, Actions};
+use crate::core::{CubeModel, Symmetry};
+use enum_iterator::all;
+use std::{error::Error, fmt::Display, str::FromStr};
+
+/// A sequence of actions, read and written in Singmaster notation, e.g. `R U R' U2 F'`.
+/// Moves are separated by whitespace, parentheses used to group moves on algorithm sheets are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Algorithm {
+    actions: Vec<Actions>,
+}
+
+/// A token that is not a valid move, `column` is the 1-based character where it starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotationError {
+    pub token: String,
+    pub column: usize,
+}
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: invalid move '{}'", self.column, self.token)
+    }
+}
+
+impl Error for NotationError {}
+
+impl Algorithm {
+    pub fn new(actions: Vec<Actions>) -> Self {
+        Self { actions }
+    }
+
+    pub fn actions(&self) -> &[Actions] {
+        &self.actions
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// The algorithm that undoes this one.
+    pub fn inverse(&self) -> Self {
+        Self::new(self.actions.iter().rev().map(|a| a.inverse()).collect())
+    }
+
+    /// The same algorithm with turns of the same layers merged, e.g. `R R` into `R2`, and turns
+    /// that cancel out removed, e.g. `R R'`. Turns around the same axis commute: they are merged
+    /// across each other and sorted in canonical order, e.g. `L R L` becomes `R L2`.
+    pub fn simplified(&self) -> Self {
+        let mut actions: Vec<Actions> = Vec::new();
+        for &action in self.actions.iter() {
+            let commuting = actions.iter().rev().take_while(|a| a.commutes_with(action)).count();
+            let start = actions.len() - commuting;
+            match actions[start..].iter().position(|a| a.same_layers(action)) {
+                Some(i) => match actions[start + i].turned(action.quarter_turns()) {
+                    Some(merged) => actions[start + i] = merged,
+                    None => {
+                        actions.remove(start + i);
+                    }
+                },
+                None => actions.push(action),
+            }
+        }
+        for commuting in actions.chunk_by_mut(|a, b| a.commutes_with(*b)) {
+            commuting.sort_by_key(|a| a.canonical_order());
+        }
+        Self::new(actions)
+    }
+
+    /// The algorithm conjugated by a symmetry, see `Actions::conjugate`.
+    pub fn conjugate(&self, symmetry: Symmetry) -> Self {
+        Self::new(self.actions.iter().map(|a| a.conjugate(symmetry)).collect())
+    }
+
+    /// True when both algorithms scramble the solved cube the same way up to rotations and
+    /// reflections of the whole cube, e.g. `R U` and `L' U'`.
+    pub fn is_symmetric_to(&self, other: &Algorithm) -> bool {
+        let mut model = CubeModel::solved();
+        self.apply_to(&mut model);
+        let mut other_model = CubeModel::solved();
+        other.apply_to(&mut other_model);
+        model.is_symmetric_to(&other_model)
+    }
+
+    pub fn apply_to(&self, target: &mut impl Actionable) {
+        for action in self.actions.iter() {
+            target.apply_action(*action);
+        }
+    }
+
+    /// Parses a single move, wide turns can be written both as `r` and `Rw`.
+    fn parse_move(token: &str) -> Option<Actions> {
+        let mut normalized = token.replace('’', "'");
+        if let Some(stripped) = normalized.strip_suffix("2'") {
+            normalized = format!("{}2", stripped);
+        }
+        let mut chars = normalized.chars();
+        if let (Some(face), Some('w')) = (chars.next(), chars.next()) {
+            if "UDFBRL".contains(face) {
+                normalized = format!("{}{}", face.to_ascii_lowercase(), chars.as_str());
+            }
+        }
+        all::<Actions>().find(|action| action.notation() == normalized)
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut actions = Vec::new();
+        let mut token = String::new();
+        let mut start = 0;
+        // a trailing space flushes the last token
+        for (i, c) in s.chars().chain(std::iter::once(' ')).enumerate() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                if !token.is_empty() {
+                    let parsed = Self::parse_move(&token).ok_or(NotationError {
+                        token: token.clone(),
+                        column: start + 1,
+                    })?;
+                    actions.push(parsed);
+                    token.clear();
+                }
+            } else {
+                if token.is_empty() {
+                    start = i;
+                }
+                token.push(c);
+            }
+        }
+        Ok(Self::new(actions))
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let moves = self
+            .actions
+            .iter()
+            .map(|action| action.notation())
+            .collect::<Vec<_>>();
+        f.write_str(&moves.join(" "))
+    }
+}
+
+impl From<Vec<Actions>> for Algorithm {
+    fn from(actions: Vec<Actions>) -> Self {
+        Self::new(actions)
+    }
+}
+
+impl Actionable for Algorithm {
+    /// Appends the action to the algorithm.
+    fn apply_action(&mut self, action: Actions) {
+        self.actions.push(action);
+    }
+}
+
+/// A solution split in named stages, as produced by solvers working stage by stage.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StagedSolution {
+    stages: Vec<(String, Algorithm)>,
+}
+
+impl StagedSolution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_stage(&mut self, name: impl Into<String>, algorithm: Algorithm) {
+        self.stages.push((name.into(), algorithm));
+    }
+
+    pub fn stages(&self) -> &[(String, Algorithm)] {
+        &self.stages
+    }
+
+    /// All the stages one after the other.
+    pub fn algorithm(&self) -> Algorithm {
+        Algorithm::new(
+            self.stages
+                .iter()
+                .flat_map(|(_, algorithm)| algorithm.actions().iter().copied())
+                .collect(),
+        )
+    }
+
+    /// Total number of moves.
+    pub fn len(&self) -> usize {
+        self.stages.iter().map(|(_, algorithm)| algorithm.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// One stage per line with its move count, e.g. `Cross (3): F R' D`.
+impl Display for StagedSolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, algorithm)) in self.stages.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{} ({})", name, algorithm.len())?;
+            if !algorithm.is_empty() {
+                write!(f, ": {}", algorithm)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_print() {
+        let algorithm: Algorithm = "R U R' U2 F'".parse().unwrap();
+        assert_eq!(
+            algorithm.actions(),
+            &[Actions::R, Actions::U, Actions::RC, Actions::U2, Actions::FC]
+        );
+        assert_eq!(algorithm.to_string(), "R U R' U2 F'");
+    }
+
+    #[test]
+    fn parse_groups_and_spacing() {
+        let algorithm: Algorithm = "  (R U R' U')\tD2' B ".parse().unwrap();
+        assert_eq!(algorithm.to_string(), "R U R' U' D2 B");
+        assert_eq!("".parse::<Algorithm>().unwrap(), Algorithm::default());
+    }
+
+    #[test]
+    fn invalid_tokens() {
+        assert_eq!(
+            "R U X U'".parse::<Algorithm>(),
+            Err(NotationError {
+                token: String::from("X"),
+                column: 5,
+            })
+        );
+        let err = "R U3".parse::<Algorithm>().unwrap_err();
+        assert_eq!(err.to_string(), "column 3: invalid move 'U3'");
+    }
+
+    #[test]
+    fn apply_to_model() {
+        let algorithm: Algorithm = "R U R' U'".parse().unwrap();
+        let mut model = CubeModel::solved();
+        for _ in 0..5 {
+            algorithm.apply_to(&mut model);
+            assert!(!model.is_solved());
+        }
+        algorithm.apply_to(&mut model);
+        assert!(model.is_solved());
+    }
+
+    #[test]
+    fn inverse_undoes_algorithm() {
+        let algorithm: Algorithm = "R U2 F' L D B'".parse().unwrap();
+        let mut model = CubeModel::solved();
+        algorithm.apply_to(&mut model);
+        algorithm.inverse().apply_to(&mut model);
+        assert!(model.is_solved());
+        assert_eq!(algorithm.inverse().to_string(), "B D' L' F U2 R'");
+    }
+
+    #[test]
+    fn every_action_round_trips() {
+        for action in all::<Actions>() {
+            let algorithm: Algorithm = action.notation().parse().unwrap();
+            assert_eq!(algorithm.actions(), &[action]);
+
+            let mut model = CubeModel::solved();
+            model.apply_action(action);
+            model.apply_action(action.inverse());
+            assert!(model.is_solved(), "{} is not undone by its inverse", action);
+        }
+    }
+
+    #[test]
+    fn wide_and_rotation_notation() {
+        let algorithm: Algorithm = "Rw Uw2 Fw' r2' M E' S2 x y' z2".parse().unwrap();
+        assert_eq!(algorithm.to_string(), "r u2 f' r2 M E' S2 x y' z2");
+    }
+
+    #[test]
+    fn simplify() {
+        let simplify = |algorithm: &str| algorithm.parse::<Algorithm>().unwrap().simplified().to_string();
+        assert_eq!(simplify("R R"), "R2");
+        assert_eq!(simplify("R R'"), "");
+        assert_eq!(simplify("R U U' R' F"), "F");
+        assert_eq!(simplify("D U D2 F"), "U D' F");
+        assert_eq!(simplify("L R L x M' M'"), "R L2 M2 x");
+        assert_eq!(simplify("U R2 R2 U"), "U2");
+        assert_eq!(simplify("R U R' U'"), "R U R' U'");
+    }
+
+    #[test]
+    fn simplified_algorithms_are_canonical() {
+        let algorithm: Algorithm = "R R U D U' L R' R' D' U B F' B2 L L' F".parse().unwrap();
+        let simplified = algorithm.simplified();
+        assert_eq!(simplified.to_string(), "R2 D R2 L U D' B'");
+        let mut model = CubeModel::solved();
+        algorithm.apply_to(&mut model);
+        simplified.inverse().apply_to(&mut model);
+        assert!(model.is_solved());
+        for pair in simplified.actions().windows(2) {
+            assert!(pair[1].follows_canonically(Some(pair[0])));
+        }
+    }
+
+    #[test]
+    fn symmetric_scrambles() {
+        let scramble: Algorithm = "R U F' D2 L".parse().unwrap();
+        assert_eq!(scramble.conjugate(Symmetry::MIRROR).to_string(), "L' U' F D2 R'");
+        for symmetry in Symmetry::all() {
+            assert!(scramble.is_symmetric_to(&scramble.conjugate(symmetry)));
+        }
+        assert!(!scramble.is_symmetric_to(&"R U F' D2 R".parse().unwrap()));
+    }
+
+    #[test]
+    fn staged_solution() {
+        let mut solution = StagedSolution::new();
+        solution.push_stage("First", "R U".parse().unwrap());
+        solution.push_stage("Second", Algorithm::default());
+        solution.push_stage("Third", "F2".parse().unwrap());
+        assert_eq!(solution.len(), 3);
+        assert_eq!(solution.algorithm().to_string(), "R U F2");
+        assert_eq!(solution.to_string(), "First (2): R U\nSecond (0)\nThird (1): F2");
+    }
+}
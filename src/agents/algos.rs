@@ -0,0 +1,280 @@
+use super::action::Actions;
+use super::heuristic::ModelHeuristic;
+use crate::core::{CubeModel, MaskedModel, PackedCube};
+
+/// Any cube can be solved in 20 face turns, or in 26 quarter turns.
+const GODS_NUMBER: usize = 20;
+const GODS_NUMBER_QUARTER_TURNS: usize = 26;
+
+type Estimate<'a> = dyn Fn(&PackedCube) -> usize + 'a;
+type IsGoal<'a> = dyn Fn(&PackedCube) -> bool + 'a;
+
+enum Bound {
+    Found,
+    /// The smallest cost above the bound met during the search, None if there is none.
+    Exceeded(Option<usize>),
+}
+
+/// Iterative deepening A* solver, as described by Korf.
+/// It runs depth-first searches bounded by a growing cost, so memory is linear in the depth of
+/// the solution, and the solution is optimal if the heuristic is admissible.
+pub struct IdaStar<H: ModelHeuristic> {
+    heuristic: H,
+    actions: Vec<Actions>,
+    /// None for God's number in the metric of the actions.
+    max_depth: Option<usize>,
+}
+
+impl<H: ModelHeuristic> IdaStar<H> {
+    /// A solver using the face turns of the half turn metric.
+    pub fn new(heuristic: H) -> Self {
+        Self {
+            heuristic,
+            actions: Actions::FACE_TURNS.to_vec(),
+            max_depth: None,
+        }
+    }
+
+    /// Searches with the given actions instead, e.g. `Actions::QUARTER_TURNS`. Unless set by
+    /// `with_max_depth`, solutions are searched up to 26 moves when all the actions are quarter
+    /// turns, 20 otherwise.
+    pub fn with_actions(mut self, actions: &[Actions]) -> Self {
+        self.actions = actions.to_vec();
+        self
+    }
+
+    /// Gives up on solutions longer than `max_depth` moves.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    fn max_depth(&self) -> usize {
+        let quarter_turns = self.actions.iter().all(|action| Actions::QUARTER_TURNS.contains(action));
+        match (self.max_depth, quarter_turns) {
+            (Some(max_depth), _) => max_depth,
+            (None, true) => GODS_NUMBER_QUARTER_TURNS,
+            (None, false) => GODS_NUMBER,
+        }
+    }
+
+    pub fn solve(&self, model: &CubeModel) -> Option<Vec<Actions>> {
+        // a state that cannot be reached would be searched forever
+        if !model.is_solvable() {
+            return None;
+        }
+        let cube = PackedCube::try_from(model).ok()?;
+        // face turns never move the centers
+        if !cube.centers_solved() {
+            return None;
+        }
+        self.solve_packed(cube)
+    }
+
+    /// Searches for the actions leading from `start` to `goal`. The start is relabeled so that
+    /// the goal becomes the solved cube, the same actions solve it and the heuristic applies.
+    pub fn solve_to(&self, start: &CubeModel, goal: &CubeModel) -> Option<Vec<Actions>> {
+        if !start.is_solvable() || !goal.is_solvable() {
+            return None;
+        }
+        let goal = PackedCube::try_from(goal).ok()?;
+        let relative = goal.inverse().multiply(&PackedCube::try_from(start).ok()?);
+        // face turns never move the centers
+        if !relative.centers_solved() {
+            return None;
+        }
+        self.solve_packed(relative)
+    }
+
+    /// Searches for the actions bringing `model` to a state matching the partial goal. The
+    /// goal is its own heuristic, see its `ModelHeuristic` implementation, the heuristic of
+    /// the solver estimating the distance to the solved cube only.
+    pub fn solve_masked(&self, model: &CubeModel, goal: &MaskedModel) -> Option<Vec<Actions>> {
+        if !model.is_solvable() {
+            return None;
+        }
+        let cube = PackedCube::try_from(model).ok()?;
+        // face turns never move the centers
+        if !goal.centers_match(&cube) {
+            return None;
+        }
+        self.solve_towards(cube, &|cube| goal.estimate_packed(cube), &|cube| goal.matches_packed(cube))
+    }
+
+    /// Searches like `solve_masked`, the solver's heuristic guiding the search as well: it must
+    /// never overestimate the moves to the partial goal, e.g. pattern databases of pieces the
+    /// goal requires solved.
+    pub fn solve_masked_guided(&self, model: &CubeModel, goal: &MaskedModel) -> Option<Vec<Actions>> {
+        if !model.is_solvable() {
+            return None;
+        }
+        let cube = PackedCube::try_from(model).ok()?;
+        if !goal.centers_match(&cube) {
+            return None;
+        }
+        self.solve_towards(
+            cube,
+            &|cube| goal.estimate_packed(cube).max(self.heuristic.estimate_packed(cube)),
+            &|cube| goal.matches_packed(cube),
+        )
+    }
+
+    fn solve_packed(&self, cube: PackedCube) -> Option<Vec<Actions>> {
+        self.solve_towards(cube, &|cube| self.heuristic.estimate_packed(cube), &PackedCube::is_solved)
+    }
+
+    fn solve_towards(&self, cube: PackedCube, estimate: &Estimate, is_goal: &IsGoal) -> Option<Vec<Actions>> {
+        let mut path = Vec::new();
+        let mut bound = estimate(&cube);
+        while bound <= self.max_depth() {
+            match self.search(cube, &mut path, bound, estimate, is_goal) {
+                Bound::Found => return Some(path),
+                Bound::Exceeded(Some(next_bound)) => bound = next_bound,
+                Bound::Exceeded(None) => return None,
+            }
+        }
+        None
+    }
+
+    /// Turning the same layers twice in a row is redundant when the two turns cancel out or
+    /// amount to another available action, which would reach the same state in fewer moves.
+    /// Opposite faces commute, turning them in the non canonical order is redundant as well.
+    fn is_redundant(&self, last: Option<&Actions>, action: Actions) -> bool {
+        match last {
+            Some(last) if last.same_layers(action) => match last.turned(action.quarter_turns()) {
+                Some(merged) => self.actions.contains(&merged),
+                None => true,
+            },
+            last => !action.follows_canonically(last.copied()),
+        }
+    }
+
+    fn search(&self, cube: PackedCube, path: &mut Vec<Actions>, bound: usize, estimate: &Estimate, is_goal: &IsGoal) -> Bound {
+        let cost = path.len() + estimate(&cube);
+        if cost > bound {
+            return Bound::Exceeded(Some(cost));
+        }
+        if is_goal(&cube) {
+            return Bound::Found;
+        }
+
+        let mut next_bound = None;
+        for &action in self.actions.iter() {
+            if self.is_redundant(path.last(), action) {
+                continue;
+            }
+            path.push(action);
+            match self.search(cube.multiply(action.packed()), path, bound, estimate, is_goal) {
+                Bound::Found => return Bound::Found,
+                Bound::Exceeded(Some(cost)) => {
+                    next_bound = Some(next_bound.map_or(cost, |b: usize| b.min(cost)))
+                }
+                Bound::Exceeded(None) => {}
+            }
+            path.pop();
+        }
+        Bound::Exceeded(next_bound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::heuristic::MisplacedCubies;
+    use crate::core::{Constraint, Face};
+    use crate::agents::{Algorithm, Pattern, PatternDatabase};
+
+    fn scrambled(scramble: &str) -> CubeModel {
+        let mut model = CubeModel::solved();
+        scramble.parse::<Algorithm>().unwrap().apply_to(&mut model);
+        model
+    }
+
+    #[test]
+    fn solved_cube_needs_no_moves() {
+        let solver = IdaStar::new(MisplacedCubies);
+        assert_eq!(solver.solve(&CubeModel::solved()), Some(vec![]));
+    }
+
+    #[test]
+    fn finds_optimal_solutions() {
+        let solver = IdaStar::new(MisplacedCubies);
+        for (scramble, length) in [("R", 1), ("R U2", 2), ("F' L D", 3), ("R U R' U'", 4)] {
+            let mut model = scrambled(scramble);
+            let solution = solver.solve(&model).unwrap();
+            assert_eq!(solution.len(), length, "{}", scramble);
+            Algorithm::from(solution).apply_to(&mut model);
+            assert!(model.is_solved());
+        }
+    }
+
+    #[test]
+    fn quarter_turn_metric() {
+        let solver = IdaStar::new(MisplacedCubies).with_actions(&Actions::QUARTER_TURNS);
+        let solution = solver.solve(&scrambled("U2 R")).unwrap();
+        assert_eq!(solution.len(), 3);
+        // some cubes need more than 20 quarter turns
+        assert_eq!(solver.max_depth(), 26);
+        assert_eq!(solver.with_max_depth(20).max_depth(), 20);
+        assert_eq!(IdaStar::new(MisplacedCubies).max_depth(), 20);
+    }
+
+    #[test]
+    fn solves_between_two_states() {
+        let solver = IdaStar::new(MisplacedCubies);
+        let start = scrambled("F2 D R' U");
+        let goal = scrambled("F2 D L B2");
+        let solution = solver.solve_to(&start, &goal).unwrap();
+        assert_eq!(solution.len(), 4);
+        let mut model = start;
+        Algorithm::from(solution).apply_to(&mut model);
+        assert_eq!(model, goal);
+        assert_eq!(solver.solve_to(&goal, &goal), Some(vec![]));
+        assert_eq!(solver.solve_to(&goal, &scrambled("y")), None);
+    }
+
+    #[test]
+    fn solves_partial_goals() {
+        let solver = IdaStar::new(MisplacedCubies);
+        let model = scrambled("R U F' L2 D B' R2");
+        for goal in [MaskedModel::cross(Face::Down), MaskedModel::edge_orientation()] {
+            let solution = solver.solve_masked(&model, &goal).unwrap();
+            let mut solved = model.clone();
+            Algorithm::from(solution).apply_to(&mut solved);
+            assert!(goal.matches(&solved));
+        }
+        let goal = MaskedModel::solved().with_corner(0, Constraint::Exact);
+        assert_eq!(solver.solve_masked(&CubeModel::solved(), &goal), Some(vec![]));
+        assert_eq!(solver.solve_masked(&scrambled("R"), &goal).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn guided_partial_goals() {
+        let pair = PatternDatabase::generate(Pattern::new(&[4], &[8]));
+        let solver = IdaStar::new(pair);
+        let model = scrambled("R U F' L2 D B' R2 U' F L' D2 B");
+        let goal = MaskedModel::solved().with_corner(4, Constraint::Exact).with_edge(8, Constraint::Exact);
+        let solution = solver.solve_masked_guided(&model, &goal).unwrap();
+        assert_eq!(solution.len(), solver.heuristic.estimate(&model));
+        let mut solved = model.clone();
+        Algorithm::from(solution).apply_to(&mut solved);
+        assert!(goal.matches(&solved));
+    }
+
+    #[test]
+    fn gives_up_past_max_depth() {
+        let solver = IdaStar::new(MisplacedCubies).with_max_depth(2);
+        assert_eq!(solver.solve(&scrambled("F' L D")), None);
+    }
+
+    #[test]
+    fn rejects_unsolvable_cubes() {
+        let model = CubeModel::solved();
+        let mut orientations = *model.orientations();
+        orientations[2][2][0] = 1;
+        let model = CubeModel::new(*model.cube_elements(), orientations);
+        assert_eq!(IdaStar::new(MisplacedCubies).solve(&model), None);
+        // solvable, but the centers are turned
+        assert_eq!(IdaStar::new(MisplacedCubies).solve(&scrambled("R x")), None);
+    }
+}
@@ -0,0 +1,323 @@
+use super::action::Actions;
+use super::algorithm::{Algorithm, StagedSolution};
+use super::algos::IdaStar;
+use super::method::{after_up, alg, applied, relative_to_centers, up_turn, Guide, Progress};
+use super::method::{BOTTOM_CORNERS, CROSS_EDGES, MIDDLE_EDGES};
+use super::pattern_database::{Pattern, PatternDatabase};
+use crate::core::{Constraint, CubeModel, Face, MaskedModel, PackedCube, TileColor};
+use std::sync::OnceLock;
+
+/// The 57 cases of the orientation of the last layer, numbered as on the usual algorithm sheets.
+pub(super) const OLL: [(&str, &str); 57] = [
+    ("OLL 1", "R U2 R2 F R F' U2 R' F R F'"),
+    ("OLL 2", "F R U R' U' F' f R U R' U' f'"),
+    ("OLL 3", "f R U R' U' f' U' F R U R' U' F'"),
+    ("OLL 4", "f R U R' U' f' U F R U R' U' F'"),
+    ("OLL 5", "r' U2 R U R' U r"),
+    ("OLL 6", "r U2 R' U' R U' r'"),
+    ("OLL 7", "r U R' U R U2 r'"),
+    ("OLL 8", "l' U' L U' L' U2 l"),
+    ("OLL 9", "R U R' U' R' F R2 U R' U' F'"),
+    ("OLL 10", "R U R' U R' F R F' R U2 R'"),
+    ("OLL 11", "r U R' U R' F R F' R U2 r'"),
+    ("OLL 12", "F R U R' U' F' U F R U R' U' F'"),
+    ("OLL 13", "F U R U' R2 F' R U R U' R'"),
+    ("OLL 14", "R' F R U R' F' R F U' F'"),
+    ("OLL 15", "r' U' r R' U' R U r' U r"),
+    ("OLL 16", "r U r' R U R' U' r U' r'"),
+    ("OLL 17", "R U R' U R' F R F' U2 R' F R F'"),
+    ("OLL 18", "r U R' U R U2 r2 U' R U' R' U2 r"),
+    ("OLL 19", "r' R U R U R' U' M' R' F R F'"),
+    ("OLL 20", "r U R' U' M2 U R U' R' U' M'"),
+    ("OLL 21", "R U2 R' U' R U R' U' R U' R'"),
+    ("OLL 22", "R U2 R2 U' R2 U' R2 U2 R"),
+    ("OLL 23", "R2 D' R U2 R' D R U2 R"),
+    ("OLL 24", "r U R' U' r' F R F'"),
+    ("OLL 25", "F' r U R' U' r' F R"),
+    ("OLL 26", "R U2 R' U' R U' R'"),
+    ("OLL 27", "R U R' U R U2 R'"),
+    ("OLL 28", "r U R' U' r' R U R U' R'"),
+    ("OLL 29", "R U R' U' R U' R' F' U' F R U R'"),
+    ("OLL 30", "F R' F R2 U' R' U' R U R' F2"),
+    ("OLL 31", "R' U' F U R U' R' F' R"),
+    ("OLL 32", "L U F' U' L' U L F L'"),
+    ("OLL 33", "R U R' U' R' F R F'"),
+    ("OLL 34", "R U R2 U' R' F R U R U' F'"),
+    ("OLL 35", "R U2 R2 F R F' R U2 R'"),
+    ("OLL 36", "L' U' L U' L' U L U L F' L' F"),
+    ("OLL 37", "F R' F' R U R U' R'"),
+    ("OLL 38", "R U R' U R U' R' U' R' F R F'"),
+    ("OLL 39", "L F' L' U' L U F U' L'"),
+    ("OLL 40", "R' F R U R' U' F' U R"),
+    ("OLL 41", "R U R' U R U2 R' F R U R' U' F'"),
+    ("OLL 42", "R' U' R U' R' U2 R F R U R' U' F'"),
+    ("OLL 43", "F' U' L' U L F"),
+    ("OLL 44", "F U R U' R' F'"),
+    ("OLL 45", "F R U R' U' F'"),
+    ("OLL 46", "R' U' R' F R F' U R"),
+    ("OLL 47", "R' U' R' F R F' R' F R F' U R"),
+    ("OLL 48", "F R U R' U' R U R' U' F'"),
+    ("OLL 49", "r U' r2 U r2 U r2 U' r"),
+    ("OLL 50", "r' U r2 U' r2 U' r2 U r'"),
+    ("OLL 51", "F U R U' R' U R U' R' F'"),
+    ("OLL 52", "R U R' U R U' B U' B' R'"),
+    ("OLL 53", "l' U2 L U L' U' L U L' U l"),
+    ("OLL 54", "r U2 R' U' R U R' U' R U' r'"),
+    ("OLL 55", "R' F R U R U' R2 F' R2 U' R' U R U R'"),
+    ("OLL 56", "r' U' r U' R' U R U' R' U R r' U r"),
+    ("OLL 57", "R U R' U' M' U R U' r'"),
+];
+
+/// The 21 cases of the permutation of the last layer, by their usual names.
+pub(super) const PLL: [(&str, &str); 21] = [
+    ("Aa", "x R' U R' D2 R U' R' D2 R2 x'"),
+    ("Ab", "x R2 D2 R U R' D2 R U' R x'"),
+    ("E", "x' R U' R' D R U R' D' R U R' D R U' R' D' x"),
+    ("F", "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"),
+    ("Ga", "R2 U R' U R' U' R U' R2 U' D R' U R D'"),
+    ("Gb", "R' U' R U D' R2 U R' U R U' R U' R2 D"),
+    ("Gc", "R2 U' R U' R U R' U R2 U D' R U' R' D"),
+    ("Gd", "R U R' U' D R2 U' R U' R' U R' U R2 D'"),
+    ("H", "M2 U M2 U2 M2 U M2"),
+    ("Ja", "x R2 F R F' R U2 r' U r U2 x'"),
+    ("Jb", "R U R' F' R U R' U' R' F R2 U' R'"),
+    ("Na", "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'"),
+    ("Nb", "R' U R U' R' F' U' F R U R' F R' F' R U' R"),
+    ("Ra", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    ("Rb", "R2 F R U R U' R' F' R U2 R' U2 R"),
+    ("T", "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    ("Ua", "M2 U M U2 M' U M2"),
+    ("Ub", "M2 U' M U2 M' U' M2"),
+    ("V", "R' U R' U' B' R' B2 U' B' U B' R B R"),
+    ("Y", "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    ("Z", "M' U M2 U M2 U M' U2 M2"),
+];
+
+/// The face turns solving the pairs, D turns would only move the cross away and back.
+const F2L_ACTIONS: [Actions; 15] = [
+    Actions::U,
+    Actions::UC,
+    Actions::U2,
+    Actions::F,
+    Actions::FC,
+    Actions::F2,
+    Actions::B,
+    Actions::BC,
+    Actions::B2,
+    Actions::R,
+    Actions::RC,
+    Actions::R2,
+    Actions::L,
+    Actions::LC,
+    Actions::L2,
+];
+
+/// A case of the last layer and the algorithm solving it, from the tables of `Cfop`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastLayerCase {
+    pub name: &'static str,
+    pub algorithm: &'static str,
+}
+
+impl LastLayerCase {
+    fn new((name, algorithm): (&'static str, &'static str)) -> Self {
+        Self { name, algorithm }
+    }
+}
+
+/// Solves the cube with the method of most speedcubers, holding white down: an optimal cross
+/// found by search, the four first two layers pairs of a corner and an edge, each one by search
+/// choosing the pair with the shortest solution, then one algorithm to orient the last layer
+/// (OLL) and one to permute it (PLL), from the tables of the 57 and 21 cases.
+pub struct Cfop {
+    cross: PatternDatabase,
+    /// Databases of the corner and edge of each pair, by side as in `SIDES`.
+    pairs: Vec<PatternDatabase>,
+}
+
+impl Cfop {
+    /// Generates the pattern databases guiding the cross and pairs searches, which takes a
+    /// moment.
+    pub fn new() -> Self {
+        Self {
+            cross: PatternDatabase::generate(Pattern::new(&[], &CROSS_EDGES)),
+            pairs: (0..4)
+                .map(|side| PatternDatabase::generate(Pattern::new(&[BOTTOM_CORNERS[side]], &[MIDDLE_EDGES[side]])))
+                .collect(),
+        }
+    }
+
+    /// The stages `Cross`, `F2L 1` to `F2L 4`, then the OLL and PLL named after their case,
+    /// e.g. `OLL 27` and `PLL T`, or `OLL skip` and `PLL skip` when the layer needed no
+    /// algorithm. The cross starts with the rotation holding white down, the PLL ends with the
+    /// last U turn. None if the model cannot be solved.
+    pub fn solve(&self, model: &CubeModel) -> Option<StagedSolution> {
+        let mut progress = Progress::new(model, TileColor::White)?;
+
+        let mut goal = MaskedModel::cross(Face::Down);
+        progress.apply_search(&IdaStar::new(Guide(vec![&self.cross])), &goal)?;
+        progress.end_stage("Cross");
+
+        let mut solved = vec![&self.cross];
+        let mut remaining: Vec<usize> = (0..4).collect();
+        for pair in 1..=4 {
+            // each search only looks for pairs solved in fewer moves than the best one so far
+            let mut best: Option<(usize, MaskedModel, Vec<Actions>)> = None;
+            for (i, side) in remaining.iter().enumerate() {
+                let next = pair_goal(&goal, *side);
+                let mut guide = solved.clone();
+                guide.push(&self.pairs[*side]);
+                let mut solver = IdaStar::new(Guide(guide)).with_actions(&F2L_ACTIONS);
+                if let Some((_, _, shortest)) = &best {
+                    solver = solver.with_max_depth(shortest.len().saturating_sub(1));
+                }
+                if let Some(solution) = solver.solve_masked_guided(&CubeModel::from(*progress.cube()), &next) {
+                    best = Some((i, next, solution));
+                }
+            }
+            let (i, next, solution) = best?;
+            progress.apply(&Algorithm::new(solution));
+            solved.push(&self.pairs[remaining.remove(i)]);
+            goal = next;
+            progress.end_stage(&format!("F2L {}", pair));
+        }
+
+        progress.last_layer()?;
+
+        progress.finish()
+    }
+
+    /// The OLL case of a model held with its last layer up and its first two layers solved,
+    /// None if they are not or if the last layer is already oriented.
+    pub fn recognize_oll(model: &CubeModel) -> Option<LastLayerCase> {
+        let cube = relative_to_centers(&PackedCube::try_from(model).ok()?);
+        Some(orientation_case(&cube)?.0)
+    }
+
+    /// The PLL case of a model held with its last layer up and all but its permutation solved,
+    /// None if it is not or if the last layer only needs a U turn.
+    pub fn recognize_pll(model: &CubeModel) -> Option<LastLayerCase> {
+        let cube = relative_to_centers(&PackedCube::try_from(model).ok()?);
+        Some(permutation_case(&cube)?.0)
+    }
+}
+
+impl Default for Cfop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The goal with the corner and edge of the pair on the right of `side` solved as well.
+fn pair_goal(goal: &MaskedModel, side: usize) -> MaskedModel {
+    goal.clone()
+        .with_corner(BOTTOM_CORNERS[side], Constraint::Exact)
+        .with_edge(MIDDLE_EDGES[side], Constraint::Exact)
+}
+
+/// The OLL case of the cube and the algorithm orienting it, preceded by the U turn setting the
+/// case up as in the table.
+pub(super) fn orientation_case(cube: &PackedCube) -> Option<(LastLayerCase, Algorithm)> {
+    let goal = MaskedModel::first_two_layers(Face::Down)
+        .with_corners_on(Face::Up, Constraint::Orientation)
+        .with_edges_on(Face::Up, Constraint::Orientation);
+    if goal.matches_packed(cube) || !MaskedModel::first_two_layers(Face::Down).matches_packed(cube) {
+        return None;
+    }
+    static ALGORITHMS: OnceLock<Vec<Algorithm>> = OnceLock::new();
+    let algorithms = ALGORITHMS.get_or_init(|| OLL.iter().map(|case| alg(case.1)).collect());
+    OLL.iter().zip(algorithms).find_map(|(case, algorithm)| {
+        (0..4)
+            .map(|q| after_up(q, algorithm))
+            .find(|algorithm| goal.matches_packed(&applied(cube, algorithm)))
+            .map(|algorithm| (LastLayerCase::new(*case), algorithm))
+    })
+}
+
+/// The PLL case of the cube and the algorithm permuting it, preceded by the U turn setting the
+/// case up as in the table. The U turn left to solve the cube is not included.
+pub(super) fn permutation_case(cube: &PackedCube) -> Option<(LastLayerCase, Algorithm)> {
+    let oriented = MaskedModel::first_two_layers(Face::Down)
+        .with_corners_on(Face::Up, Constraint::Orientation)
+        .with_edges_on(Face::Up, Constraint::Orientation);
+    let solved = MaskedModel::solved().with_all(Constraint::Exact);
+    let solved_by_turn = |cube: &PackedCube| (0..4).any(|q| solved.matches_packed(&applied(cube, &up_turn(q))));
+    if !oriented.matches_packed(cube) || solved_by_turn(cube) {
+        return None;
+    }
+    static ALGORITHMS: OnceLock<Vec<Algorithm>> = OnceLock::new();
+    let algorithms = ALGORITHMS.get_or_init(|| PLL.iter().map(|case| alg(case.1)).collect());
+    PLL.iter().zip(algorithms).find_map(|(case, algorithm)| {
+        (0..4)
+            .map(|q| after_up(q, algorithm))
+            .find(|algorithm| solved_by_turn(&applied(cube, algorithm)))
+            .map(|algorithm| (LastLayerCase::new(*case), algorithm))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::method::testing::{assert_solves, random_scramble, scrambled};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn solver() -> &'static Cfop {
+        static SOLVER: OnceLock<Cfop> = OnceLock::new();
+        SOLVER.get_or_init(Cfop::new)
+    }
+
+    #[test]
+    fn case_algorithms_keep_the_first_two_layers() {
+        let first_two_layers = MaskedModel::first_two_layers(Face::Down);
+        for (name, algorithm) in OLL.iter().chain(PLL.iter()) {
+            let cube = applied(&PackedCube::SOLVED, &alg(algorithm));
+            assert!(first_two_layers.matches_packed(&cube), "{}", name);
+        }
+    }
+
+    /// Each case is recognized as itself whatever the U turns around it, so the cases of the
+    /// tables are all different.
+    #[test]
+    fn recognizes_every_case() {
+        for (table, recognize) in [
+            (&OLL[..], orientation_case as fn(&PackedCube) -> Option<(LastLayerCase, Algorithm)>),
+            (&PLL[..], permutation_case),
+        ] {
+            for (name, algorithm) in table {
+                for q in 0..4 {
+                    let scramble = after_up(q, &alg(algorithm).inverse());
+                    let cube = applied(&applied(&PackedCube::SOLVED, &scramble), &up_turn(q));
+                    let (case, solution) = recognize(&cube).unwrap();
+                    assert_eq!(case.name, *name);
+                    let cube = applied(&cube, &solution);
+                    assert_eq!(recognize(&cube), None);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn recognizes_models() {
+        let case = Cfop::recognize_oll(&scrambled("R U2 R' U' R U' R'")).unwrap();
+        assert_eq!(case.name, "OLL 27");
+        let case = Cfop::recognize_pll(&scrambled("y R U R' U' R' F R2 U' R' U' R U R' F'")).unwrap();
+        assert_eq!(case.name, "T");
+        assert_eq!(Cfop::recognize_oll(&scrambled("R")), None);
+        assert_eq!(Cfop::recognize_pll(&scrambled("U")), None);
+    }
+
+    #[test]
+    fn solves_scrambles() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..3 {
+            let model = random_scramble(&mut rng, 25);
+            let solution = solver().solve(&model).unwrap();
+            let names: Vec<_> = solution.stages().iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names[..5], ["Cross", "F2L 1", "F2L 2", "F2L 3", "F2L 4"]);
+            assert!(names[5].starts_with("OLL") && names[6].starts_with("PLL"));
+            // white starts up, the cross takes a rotation and at most 8 moves
+            assert!(solution.stages()[0].1.len() <= 9);
+            assert_solves(&solution, &model);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use super::action::Actions;
+use super::heuristic::HeuristicRegistry;
+use crate::core::PackedCube;
+use std::{collections::HashMap, fmt::Display};
+
+/// How a heuristic compares with the exact distances of the states sampled by
+/// `evaluate_heuristics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeuristicReport {
+    pub name: String,
+    pub samples: usize,
+    /// Sampled states whose estimate exceeds their distance.
+    pub admissibility_violations: usize,
+    /// Pairs of a sampled state and a face turn where the estimate drops by more than one move.
+    pub consistency_violations: usize,
+    /// Mean of the absolute difference between the estimates and the distances.
+    pub mean_error: f64,
+}
+
+impl HeuristicReport {
+    pub fn is_admissible(&self) -> bool {
+        self.admissibility_violations == 0
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.consistency_violations == 0
+    }
+}
+
+impl Display for HeuristicReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} samples, {} admissibility violations, {} consistency violations, mean error {:.2}",
+            self.name, self.samples, self.admissibility_violations, self.consistency_violations, self.mean_error
+        )
+    }
+}
+
+/// Exact distance in face turns of every state at most `max_depth` moves from the solved cube,
+/// by breadth first search.
+fn exact_distances(max_depth: usize) -> HashMap<PackedCube, usize> {
+    let mut distances = HashMap::from([(PackedCube::SOLVED, 0)]);
+    let mut layer = vec![PackedCube::SOLVED];
+    for depth in 1..=max_depth {
+        let mut next_layer = Vec::new();
+        for cube in layer {
+            for action in Actions::FACE_TURNS {
+                let next = cube.multiply(action.packed());
+                distances.entry(next).or_insert_with(|| {
+                    next_layer.push(next);
+                    depth
+                });
+            }
+        }
+        layer = next_layer;
+    }
+    distances
+}
+
+/// Compares every heuristic of the registry with the exact distances of states at most
+/// `max_depth` face turns from the solved cube. At most `samples` states are evenly picked
+/// among them, spread over the depths. The layers grow quickly past a depth of 4 or 5.
+pub fn evaluate_heuristics(registry: &HeuristicRegistry, max_depth: usize, samples: usize) -> Vec<HeuristicReport> {
+    let distances = exact_distances(max_depth);
+    let mut states: Vec<(usize, PackedCube)> = distances.iter().map(|(cube, distance)| (*distance, *cube)).collect();
+    states.sort();
+    let per_depth = samples.div_ceil(max_depth + 1).max(1);
+    let mut sampled = Vec::new();
+    for depth in 0..=max_depth {
+        let layer: Vec<_> = states.iter().filter(|(distance, _)| *distance == depth).collect();
+        let step = layer.len().div_ceil(per_depth).max(1);
+        sampled.extend(layer.into_iter().step_by(step).copied());
+    }
+
+    registry
+        .iter()
+        .map(|(name, heuristic)| {
+            let mut admissibility_violations = 0;
+            let mut consistency_violations = 0;
+            let mut total_error = 0;
+            for (distance, cube) in sampled.iter() {
+                let estimate = heuristic.estimate_packed(cube);
+                if estimate > *distance {
+                    admissibility_violations += 1;
+                }
+                total_error += estimate.abs_diff(*distance);
+                consistency_violations += Actions::FACE_TURNS
+                    .iter()
+                    .filter(|action| estimate > 1 + heuristic.estimate_packed(&cube.multiply(action.packed())))
+                    .count();
+            }
+            HeuristicReport {
+                name: name.to_string(),
+                samples: sampled.len(),
+                admissibility_violations,
+                consistency_violations,
+                mean_error: total_error as f64 / sampled.len() as f64,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::CubeModel;
+    use std::sync::Arc;
+
+    #[test]
+    fn exact_distances_by_depth() {
+        let distances = exact_distances(2);
+        assert_eq!(distances.len(), 1 + 18 + 243);
+        assert_eq!(distances[&PackedCube::SOLVED], 0);
+    }
+
+    #[test]
+    fn reports_violations() {
+        let reports = evaluate_heuristics(&HeuristicRegistry::default(), 3, 60);
+        let report = |name: &str| reports.iter().find(|report| report.name == name).unwrap();
+        assert_eq!(reports.len(), HeuristicRegistry::default().names().count());
+        assert!(report("misplaced-cubies").is_admissible());
+        assert!(report("misplaced-cubies").is_consistent());
+        assert!(!report("move-distance").is_admissible());
+        assert!(!report("misplaced-tiles").is_consistent());
+        assert!(reports.iter().all(|report| report.samples == reports[0].samples));
+    }
+
+    #[test]
+    fn perfect_heuristic() {
+        let mut registry = HeuristicRegistry::new();
+        registry.register("exact", Arc::new(|model: &CubeModel| {
+            let cube = PackedCube::try_from(model).unwrap();
+            exact_distances(2).get(&cube).copied().unwrap_or(3)
+        }));
+        registry.register("zero", Arc::new(|_: &CubeModel| 0));
+        let reports = evaluate_heuristics(&registry, 2, 10);
+        assert_eq!(reports[0].mean_error, 0.0);
+        assert!(reports[0].is_admissible() && reports[0].is_consistent());
+        assert!(reports[1].is_admissible() && reports[1].is_consistent());
+        assert!(reports[1].mean_error > 1.0);
+    }
+}
@@ -1,35 +1,167 @@
-use super::state_space::CubeState;
-use crate::core::SOLVED_INDEX_MAP;
+use crate::core::{CubeModel, MaskedModel, PackedCube, ValidationError, CORNER_SLOTS, EDGE_SLOTS, SOLVED_INDEX_MAP};
+use std::sync::{Arc, OnceLock};
 
-trait MoveDistance {
-    fn move_distance(&self) -> usize;
+/// Estimates the number of moves needed to solve a model, for solvers that search on models.
+/// Solvers that promise optimal solutions need the estimate to be admissible, that is to never
+/// exceed the real distance.
+pub trait ModelHeuristic {
+    fn estimate(&self, model: &CubeModel) -> usize;
+
+    /// The same estimate for a packed cube, called by the solvers searching on packed cubes.
+    /// The default converts the cube back to a model, heuristics should compute it directly.
+    fn estimate_packed(&self, cube: &PackedCube) -> usize {
+        self.estimate(&CubeModel::from(*cube))
+    }
+}
+
+impl<F: Fn(&CubeModel) -> usize> ModelHeuristic for F {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        self(model)
+    }
+}
+
+/// A heuristic shared between solvers and threads, as handed out by `HeuristicRegistry`.
+pub type SharedHeuristic = Arc<dyn ModelHeuristic + Send + Sync>;
+
+/// Heuristics by name, so that the one a solver uses can be chosen at runtime, e.g. from the
+/// command line. The default registry holds the heuristics of this module.
+#[derive(Clone)]
+pub struct HeuristicRegistry {
+    heuristics: Vec<(String, SharedHeuristic)>,
+}
+
+impl HeuristicRegistry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self {
+            heuristics: Vec::new(),
+        }
+    }
+
+    /// Adds a heuristic, replacing the one registered under the same name if any.
+    pub fn register(&mut self, name: impl Into<String>, heuristic: SharedHeuristic) {
+        let name = name.into();
+        match self.heuristics.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, registered)) => *registered = heuristic,
+            None => self.heuristics.push((name, heuristic)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<SharedHeuristic> {
+        self.heuristics
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, heuristic)| heuristic.clone())
+    }
+
+    /// The names of the heuristics, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.heuristics.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SharedHeuristic)> {
+        self.heuristics.iter().map(|(name, heuristic)| (name.as_str(), heuristic))
+    }
+}
+
+impl Default for HeuristicRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register("misplaced-cubies", Arc::new(MisplacedCubies));
+        registry.register("misplaced-tiles", Arc::new(MisplacedTiles));
+        registry.register("move-distance", Arc::new(MoveDistance));
+        registry.register("color-distance", Arc::new(ColorDistance));
+        registry.register("color-disparity", Arc::new(ColorDisparity));
+        registry
+    }
+}
+
+/// Estimates the distance to a goal other than the solved cube, relabeling the cubes so that
+/// the goal becomes the solved cube before handing them to `heuristic`. The relabeled cube is
+/// as many moves away from the solved cube as the cube is from the goal.
+#[derive(Clone)]
+pub struct RelativeHeuristic {
+    heuristic: SharedHeuristic,
+    goal_inverse: PackedCube,
 }
 
-trait MisplacedTiles {
-    fn misplaced_tiles(&self) -> usize;
+impl RelativeHeuristic {
+    /// Fails if the goal is not a valid cube.
+    pub fn new(heuristic: SharedHeuristic, goal: &CubeModel) -> Result<Self, ValidationError> {
+        Ok(Self {
+            heuristic,
+            goal_inverse: PackedCube::try_from(goal)?.inverse(),
+        })
+    }
 }
 
-trait ColorDistance {
-    fn color_distance(&self) -> usize;
+impl ModelHeuristic for RelativeHeuristic {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        self.estimate_packed(&PackedCube::try_from(model).expect("Invalid model"))
+    }
+
+    fn estimate_packed(&self, cube: &PackedCube) -> usize {
+        self.heuristic.estimate_packed(&self.goal_inverse.multiply(cube))
+    }
 }
 
-pub trait Heuristic {
-    fn heuristic(&self) -> usize;
+/// Admissible heuristic counting the corners and edges out of place or twisted.
+/// A face turn moves 4 corners and 4 edges, so at least a quarter of either count of moves is needed.
+pub struct MisplacedCubies;
+
+impl ModelHeuristic for MisplacedCubies {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        let elements = model.cube_elements();
+        let orientations = model.orientations();
+        let misplaced = |(x, y, z): (usize, usize, usize)| {
+            elements[x][y][z].home() != (x, y, z) || orientations[x][y][z] != 0
+        };
+        let corners = CORNER_SLOTS.iter().filter(|(pos, _)| misplaced(*pos)).count();
+        let edges = EDGE_SLOTS.iter().filter(|(pos, _)| misplaced(*pos)).count();
+        corners.div_ceil(4).max(edges.div_ceil(4))
+    }
+
+    fn estimate_packed(&self, cube: &PackedCube) -> usize {
+        let corners = (0..8).filter(|slot| cube.corner(*slot) != (*slot as u8, 0)).count();
+        let edges = (0..12).filter(|slot| cube.edge(*slot) != (*slot as u8, 0)).count();
+        corners.div_ceil(4).max(edges.div_ceil(4))
+    }
+}
+
+/// Admissible estimate of the distance to a partial goal, counting the corners and edges that
+/// break their constraint like `MisplacedCubies`. Cubes with other centers than the target are
+/// never estimated as matching.
+impl ModelHeuristic for MaskedModel {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        self.estimate_packed(&PackedCube::try_from(model).expect("Invalid model"))
+    }
+
+    fn estimate_packed(&self, cube: &PackedCube) -> usize {
+        let (corners, edges) = self.mismatches(cube);
+        let estimate = corners.div_ceil(4).max(edges.div_ceil(4));
+        match estimate == 0 && !self.centers_match(cube) {
+            true => 1,
+            false => estimate,
+        }
+    }
 }
 
-impl MoveDistance for CubeState {
-    fn move_distance(&self) -> usize {
-        let elements = self.elements();
+/// Sum over the cubies of the Manhattan distance to their home. Not admissible, a face turn
+/// moves eight cubies by up to two each.
+pub struct MoveDistance;
+
+impl ModelHeuristic for MoveDistance {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        let elements = model.cube_elements();
         let mut distance = 0;
-        for i in 0..3 {
-            for j in 0..3 {
-                for k in 0..3 {
-                    let element = elements[i][j][k];
-                    let index = SOLVED_INDEX_MAP.iter().filter(|(_, e)| *e == element).next().unwrap().0;
+        for (i, plane) in elements.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, &element) in row.iter().enumerate() {
+                    let index = SOLVED_INDEX_MAP.iter().find(|(_, e)| *e == element).unwrap().0;
                     let (l, m, n) = index;
-                    distance += (i as isize - l as isize).abs() as usize;
-                    distance += (j as isize - m as isize).abs() as usize;
-                    distance += (k as isize - n as isize).abs() as usize;
+                    distance += i.abs_diff(l);
+                    distance += j.abs_diff(m);
+                    distance += k.abs_diff(n);
                 }
             }
         }
@@ -37,15 +169,18 @@ impl MoveDistance for CubeState {
     }
 }
 
-impl MisplacedTiles for CubeState {
-    fn misplaced_tiles(&self) -> usize {
-        let elements = self.elements();
+/// Number of cubies out of their home, whatever their orientation. Not admissible, a face
+/// turn moves eight cubies.
+pub struct MisplacedTiles;
+
+impl ModelHeuristic for MisplacedTiles {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        let elements = model.cube_elements();
         let mut distance = 0;
-        for i in 0..3 {
-            for j in 0..3 {
-                for k in 0..3 {
-                    let element = elements[i][j][k];
-                    let index = SOLVED_INDEX_MAP.iter().filter(|(_, e)| *e == element).next().unwrap().0;
+        for (i, plane) in elements.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, &element) in row.iter().enumerate() {
+                    let index = SOLVED_INDEX_MAP.iter().find(|(_, e)| *e == element).unwrap().0;
                     let (l, m, n) = index;
                     if i != l || j != m || k != n {
                         distance += 1;
@@ -57,15 +192,17 @@ impl MisplacedTiles for CubeState {
     }
 }
 
-impl ColorDistance for CubeState {
-    fn color_distance(&self) -> usize {
-        let elements = self.elements();
+/// Number of coordinates in which the cubies are away from their home. Not admissible.
+pub struct ColorDistance;
+
+impl ModelHeuristic for ColorDistance {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        let elements = model.cube_elements();
         let mut distance = 0;
-        for i in 0..3 {
-            for j in 0..3 {
-                for k in 0..3 {
-                    let element = elements[i][j][k];
-                    let index = SOLVED_INDEX_MAP.iter().filter(|(_, e)| *e == element).next().unwrap().0;
+        for (i, plane) in elements.iter().enumerate() {
+            for (j, row) in plane.iter().enumerate() {
+                for (k, &element) in row.iter().enumerate() {
+                    let index = SOLVED_INDEX_MAP.iter().find(|(_, e)| *e == element).unwrap().0;
                     let (l, m, n) = index;
                     if i != l {
                         distance += 1;
@@ -83,85 +220,84 @@ impl ColorDistance for CubeState {
     }
 }
 
-trait ColorDisparity {
-    fn color_disparity(&self) -> usize;
+/// Number of neighbouring cubies sharing a color missing from the solved cube, where every
+/// cubie shares a color with its neighbours of the same face. Not admissible.
+pub struct ColorDisparity;
+
+impl ModelHeuristic for ColorDisparity {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        static SOLVED: OnceLock<usize> = OnceLock::new();
+        let solved = *SOLVED.get_or_init(|| same_color_neighbors(&CubeModel::solved()));
+        solved.saturating_sub(same_color_neighbors(model))
+    }
 }
 
-impl ColorDisparity for CubeState {
-    fn color_disparity(&self) -> usize {
-        let elements = self.elements();
-        let mut same_color_neighbors = 0;
-        for i in 0..3 {
-            for j in 0..3 {
-                for k in 0..3 {
-                    let element = elements[i][j][k];
-                    let colors = element.to_tile_colors();
-                    // check if the element has neighbors with the same color
-                    if i > 0 {
-                        let other_elements = elements[i - 1][j][k];
-                        let other_colors = other_elements.to_tile_colors();
-                        for color in colors.iter() {
-                            if other_colors.contains(color) {
-                                same_color_neighbors += 1;
-                            }
+/// Counts the colors the cubies share with their neighbours.
+fn same_color_neighbors(model: &CubeModel) -> usize {
+    let elements = model.cube_elements();
+    let mut same_color_neighbors = 0;
+    for i in 0..3 {
+        for j in 0..3 {
+            for k in 0..3 {
+                let element = elements[i][j][k];
+                let colors = element.to_tile_colors();
+                // check if the element has neighbors with the same color
+                if i > 0 {
+                    let other_elements = elements[i - 1][j][k];
+                    let other_colors = other_elements.to_tile_colors();
+                    for color in colors.iter() {
+                        if other_colors.contains(color) {
+                            same_color_neighbors += 1;
                         }
                     }
-                    if i < 2 {
-                        let other_elements = elements[i + 1][j][k];
-                        let other_colors = other_elements.to_tile_colors();
-                        for color in colors.iter() {
-                            if other_colors.contains(color) {
-                                same_color_neighbors += 1;
-                            }
+                }
+                if i < 2 {
+                    let other_elements = elements[i + 1][j][k];
+                    let other_colors = other_elements.to_tile_colors();
+                    for color in colors.iter() {
+                        if other_colors.contains(color) {
+                            same_color_neighbors += 1;
                         }
                     }
-                    if j > 0 {
-                        let other_elements = elements[i][j - 1][k];
-                        let other_colors = other_elements.to_tile_colors();
-                        for color in colors.iter() {
-                            if other_colors.contains(color) {
-                                same_color_neighbors += 1;
-                            }
+                }
+                if j > 0 {
+                    let other_elements = elements[i][j - 1][k];
+                    let other_colors = other_elements.to_tile_colors();
+                    for color in colors.iter() {
+                        if other_colors.contains(color) {
+                            same_color_neighbors += 1;
                         }
                     }
-                    if j < 2 {
-                        let other_elements = elements[i][j + 1][k];
-                        let other_colors = other_elements.to_tile_colors();
-                        for color in colors.iter() {
-                            if other_colors.contains(color) {
-                                same_color_neighbors += 1;
-                            }
+                }
+                if j < 2 {
+                    let other_elements = elements[i][j + 1][k];
+                    let other_colors = other_elements.to_tile_colors();
+                    for color in colors.iter() {
+                        if other_colors.contains(color) {
+                            same_color_neighbors += 1;
                         }
                     }
-                    if k > 0 {
-                        let other_elements = elements[i][j][k - 1];
-                        let other_colors = other_elements.to_tile_colors();
-                        for color in colors.iter() {
-                            if other_colors.contains(color) {
-                                same_color_neighbors += 1;
-                            }
+                }
+                if k > 0 {
+                    let other_elements = elements[i][j][k - 1];
+                    let other_colors = other_elements.to_tile_colors();
+                    for color in colors.iter() {
+                        if other_colors.contains(color) {
+                            same_color_neighbors += 1;
                         }
                     }
-                    if k < 2 {
-                        let other_elements = elements[i][j][k + 1];
-                        let other_colors = other_elements.to_tile_colors();
-                        for color in colors.iter() {
-                            if other_colors.contains(color) {
-                                same_color_neighbors += 1;
-                            }
+                }
+                if k < 2 {
+                    let other_elements = elements[i][j][k + 1];
+                    let other_colors = other_elements.to_tile_colors();
+                    for color in colors.iter() {
+                        if other_colors.contains(color) {
+                            same_color_neighbors += 1;
                         }
                     }
                 }
             }
         }
-        same_color_neighbors
     }
+    same_color_neighbors
 }
-
-impl Heuristic for CubeState {
-    fn heuristic(&self) -> usize {
-        // self.misplaced_tiles() + self.move_distance() + self.color_distance()
-        // self.misplaced_tiles() + self.color_distance()
-        self.color_disparity() + self.misplaced_tiles()
-    }
-}
\ No newline at end of file
@@ -0,0 +1,213 @@
+use super::algorithm::{Algorithm, StagedSolution};
+use super::method::{after_up, alg, applied, around_up, corner_slot, edge_slot, repeated, up_turn, Progress};
+use super::method::{BOTTOM_CORNERS, CROSS_EDGES, MIDDLE_EDGES, TOP_CORNERS};
+use crate::core::{Constraint, CubeModel, Face, MaskedModel, TileColor};
+
+/// The algorithms of the method by name, written for the front side or its right slots.
+const ALGORITHMS: [(&str, &str); 10] = [
+    // white cross
+    ("Edge down", "F2"),
+    ("Edge out of the middle", "F' U' F"),
+    ("Flipped edge down", "U' R' F R"),
+    // white corners, the trigger being repeated until the corner is solved
+    ("Trigger", "R U R' U'"),
+    // middle layer
+    ("Right edge", "U R U' R' U' F' U F"),
+    ("Left edge", "U' L' U L U F U' F'"),
+    // last layer
+    ("Yellow cross", "F R U R' U' F'"),
+    ("Sune", "R U R' U R U2 R'"),
+    ("Corner cycle", "U R U' L' U R' U' L"),
+    ("Corner twist", "R' D' R D"),
+];
+
+/// The algorithm of the table with the given name.
+fn algorithm(name: &str) -> Algorithm {
+    ALGORITHMS.iter().find(|(n, _)| *n == name).map(|(_, notation)| alg(notation)).unwrap()
+}
+
+/// Solves the cube the way beginners learn it, layer by layer with a handful of algorithms,
+/// holding white down: white cross, white corners, middle layer, then the yellow cross, the
+/// yellow edges, and the permutation and orientation of the yellow corners.
+/// The solutions are long, about a hundred moves, but every stage can be followed by hand.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LayerByLayer;
+
+impl LayerByLayer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// One stage per step of the method, the first one starting with the rotation holding white
+    /// down. A model whose centers are turned is solved relative to them. None if the model
+    /// cannot be solved.
+    pub fn solve(&self, model: &CubeModel) -> Option<StagedSolution> {
+        let mut progress = Progress::new(model, TileColor::White)?;
+
+        let mut goal = MaskedModel::solved();
+        for (side, slot) in CROSS_EDGES.into_iter().enumerate() {
+            goal = goal.with_edge(slot, Constraint::Exact);
+            cross_edge(&mut progress, side, &goal)?;
+        }
+        progress.end_stage("White cross");
+
+        for (side, slot) in BOTTOM_CORNERS.into_iter().enumerate() {
+            goal = goal.with_corner(slot, Constraint::Exact);
+            bottom_corner(&mut progress, side, &goal)?;
+        }
+        progress.end_stage("White corners");
+
+        for (side, slot) in MIDDLE_EDGES.into_iter().enumerate() {
+            goal = goal.with_edge(slot, Constraint::Exact);
+            middle_edge(&mut progress, side, &goal)?;
+        }
+        progress.end_stage("Middle layer");
+
+        let goal = goal.with_edges_on(Face::Up, Constraint::Orientation);
+        let candidates: Vec<_> = (0..4).map(|q| after_up(q, &algorithm("Yellow cross"))).collect();
+        progress.apply_shortest(&candidates, &goal, 3)?;
+        progress.end_stage("Yellow cross");
+
+        let goal = goal.with_edges_on(Face::Up, Constraint::Exact);
+        let candidates: Vec<_> = (1..4)
+            .map(up_turn)
+            .chain((0..4).map(|q| after_up(q, &algorithm("Sune"))))
+            .collect();
+        progress.apply_shortest(&candidates, &goal, 4)?;
+        progress.end_stage("Yellow edges");
+
+        let goal = goal.with_corners_on(Face::Up, Constraint::Placed);
+        let candidates: Vec<_> = (0..4)
+            .map(|side| algorithm("Corner cycle").conjugate(around_up(side)))
+            .collect();
+        progress.apply_shortest(&candidates, &goal, 3)?;
+        progress.end_stage("Yellow corner permutation");
+
+        // the first two layers are broken while twisting the corners and restored at the end, the
+        // corner in front is twisted by pairs of triggers, a single one takes it out
+        let twisted = MaskedModel::solved().with_corner(0, Constraint::Orientation);
+        for _ in 0..4 {
+            if !twisted.matches_packed(progress.cube()) {
+                progress.apply_first([2, 4].map(|n| repeated(&algorithm("Corner twist"), n)), &twisted)?;
+            }
+            progress.apply(&up_turn(1));
+        }
+        progress.apply_first((0..4).map(up_turn), &MaskedModel::solved().with_all(Constraint::Exact))?;
+        progress.end_stage("Yellow corner orientation");
+
+        progress.finish()
+    }
+}
+
+/// Brings the D edge of `side` home, the previous ones staying solved.
+fn cross_edge(progress: &mut Progress, side: usize, goal: &MaskedModel) -> Option<()> {
+    if goal.matches_packed(progress.cube()) {
+        return Some(());
+    }
+    // first up to the U layer
+    let slot = edge_slot(progress.cube(), CROSS_EDGES[side]);
+    if let Some(at) = CROSS_EDGES.iter().position(|s| *s == slot) {
+        progress.apply(&algorithm("Edge down").conjugate(around_up(at)));
+    } else if let Some(at) = MIDDLE_EDGES.iter().position(|s| *s == slot) {
+        progress.apply(&algorithm("Edge out of the middle").conjugate(around_up(at)));
+    }
+    let inserts = [algorithm("Edge down"), algorithm("Flipped edge down")];
+    let candidates = (0..4).flat_map(|q| inserts.iter().map(move |insert| after_up(q, &insert.conjugate(around_up(side)))));
+    progress.apply_first(candidates, goal)
+}
+
+/// Brings the D corner on the right of `side` home, the previous ones staying solved.
+fn bottom_corner(progress: &mut Progress, side: usize, goal: &MaskedModel) -> Option<()> {
+    if goal.matches_packed(progress.cube()) {
+        return Some(());
+    }
+    let trigger = algorithm("Trigger");
+    let slot = corner_slot(progress.cube(), BOTTOM_CORNERS[side]);
+    if let Some(at) = BOTTOM_CORNERS.iter().position(|s| *s == slot) {
+        progress.apply(&trigger.conjugate(around_up(at)));
+    }
+    let above = (0..4)
+        .map(up_turn)
+        .find(|up| corner_slot(&applied(progress.cube(), up), BOTTOM_CORNERS[side]) == TOP_CORNERS[side])?;
+    progress.apply(&above);
+    let trigger = trigger.conjugate(around_up(side));
+    progress.apply_first((1..=6).map(|n| repeated(&trigger, n)), goal)
+}
+
+/// Brings the middle edge on the right of `side` home, the previous ones staying solved.
+fn middle_edge(progress: &mut Progress, side: usize, goal: &MaskedModel) -> Option<()> {
+    if goal.matches_packed(progress.cube()) {
+        return Some(());
+    }
+    let right = algorithm("Right edge");
+    let left = algorithm("Left edge");
+    // pushed out to the U layer by inserting any other edge in its place
+    let slot = edge_slot(progress.cube(), MIDDLE_EDGES[side]);
+    if let Some(at) = MIDDLE_EDGES.iter().position(|s| *s == slot) {
+        progress.apply(&right.conjugate(around_up(at)));
+    }
+    let right = right.conjugate(around_up(side));
+    let left = left.conjugate(around_up(side + 1));
+    let candidates = (0..4).flat_map(|q| [after_up(q, &right), after_up(q, &left)]);
+    progress.apply_first(candidates, goal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::method::testing::{assert_solves, random_scramble, scrambled};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn solved_cube() {
+        // only the rotation holding white down
+        let solution = LayerByLayer.solve(&CubeModel::solved()).unwrap();
+        assert_eq!(solution.len(), 1);
+        let names: Vec<_> = solution.stages().iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "White cross",
+                "White corners",
+                "Middle layer",
+                "Yellow cross",
+                "Yellow edges",
+                "Yellow corner permutation",
+                "Yellow corner orientation"
+            ]
+        );
+    }
+
+    #[test]
+    fn solves_scrambles() {
+        for scramble in [
+            "R U F' L2 D B'",
+            "D2 F' U2 L' B R2 D' F2 U R' B2 L D2 F U' R2 B' L2 U F' D R B2 L' U2",
+            "F R U R' U' F' U2 B L2 D' R F2 U L' B2 D R' F U2 L D2 B' R2 U' F2",
+            "x y' R U2 L' F D2 B' U R2 D L' F2",
+        ] {
+            let model = scrambled(scramble);
+            let solution = LayerByLayer.solve(&model).unwrap();
+            assert_eq!(solution.stages().len(), 7);
+            assert_solves(&solution, &model);
+        }
+    }
+
+    #[test]
+    fn solves_random_scrambles() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            let model = random_scramble(&mut rng, 30);
+            assert_solves(&LayerByLayer.solve(&model).unwrap(), &model);
+        }
+    }
+
+    #[test]
+    fn unsolvable_cube() {
+        let model = CubeModel::solved();
+        let mut orientations = *model.orientations();
+        orientations[2][2][0] = 1;
+        let model = CubeModel::new(*model.cube_elements(), orientations);
+        assert!(LayerByLayer.solve(&model).is_none());
+    }
+}
@@ -0,0 +1,274 @@
+use super::action::Actions;
+use super::algorithm::{Algorithm, StagedSolution};
+use super::algos::IdaStar;
+use super::cfop::{orientation_case, permutation_case};
+use super::heuristic::ModelHeuristic;
+use super::pattern_database::PatternDatabase;
+use crate::core::{Constraint, CubeModel, Face, MaskedModel, PackedCube, Symmetry, TileColor, CENTER_SLOTS};
+use std::collections::{HashSet, VecDeque};
+
+/// Whole cube rotations, to hold the cube with a chosen face down.
+const ROTATIONS: [Actions; 9] = [
+    Actions::X,
+    Actions::XC,
+    Actions::X2,
+    Actions::Y,
+    Actions::YC,
+    Actions::Y2,
+    Actions::Z,
+    Actions::ZC,
+    Actions::Z2,
+];
+
+/// The four sides around the U face, in the order `around_up` turns them into each other.
+pub(super) const SIDES: [Face; 4] = [Face::Front, Face::Right, Face::Back, Face::Left];
+
+/// Slots of the D edges, the D corners and the U corners above them by side, in the order of
+/// `SIDES`: the front one, or the front right one for corners.
+pub(super) const CROSS_EDGES: [usize; 4] = [5, 4, 7, 6];
+pub(super) const BOTTOM_CORNERS: [usize; 4] = [4, 7, 6, 5];
+pub(super) const TOP_CORNERS: [usize; 4] = [0, 3, 2, 1];
+/// Slots of the middle layer edges by side, the front right one.
+pub(super) const MIDDLE_EDGES: [usize; 4] = [8, 11, 10, 9];
+
+/// Parses an algorithm written in the source.
+pub(super) fn alg(notation: &str) -> Algorithm {
+    notation.parse().unwrap()
+}
+
+/// The rotation around the U axis taking the front to `SIDES[quarters]`. Conjugating an
+/// algorithm written for the front by it gives the same algorithm performed on that side.
+pub(super) fn around_up(quarters: usize) -> Symmetry {
+    Symmetry::all()
+        .find(|symmetry| {
+            !symmetry.is_mirror()
+                && symmetry.face(Face::Up) == Face::Up
+                && symmetry.face(Face::Front) == SIDES[quarters % 4]
+        })
+        .unwrap()
+}
+
+/// The U turn by the given clockwise quarter turns, empty if they cancel out.
+pub(super) fn up_turn(quarters: usize) -> Algorithm {
+    Algorithm::new(Actions::face_turn(Face::Up, quarters as u8).into_iter().collect())
+}
+
+/// The first algorithm followed by the second one.
+pub(super) fn joined(first: &Algorithm, second: &Algorithm) -> Algorithm {
+    Algorithm::new([first.actions(), second.actions()].concat())
+}
+
+/// The U turn by the given quarter turns followed by the algorithm, to set a case up.
+pub(super) fn after_up(quarters: usize, algorithm: &Algorithm) -> Algorithm {
+    joined(&up_turn(quarters), algorithm)
+}
+
+/// The algorithm performed `times` times in a row.
+pub(super) fn repeated(algorithm: &Algorithm, times: usize) -> Algorithm {
+    Algorithm::new(algorithm.actions().repeat(times))
+}
+
+/// The slot holding the corner piece, which is named after its home slot.
+pub(super) fn corner_slot(cube: &PackedCube, piece: usize) -> usize {
+    (0..8).find(|slot| cube.corner(*slot).0 as usize == piece).unwrap()
+}
+
+/// The slot holding the edge piece, which is named after its home slot.
+pub(super) fn edge_slot(cube: &PackedCube, piece: usize) -> usize {
+    (0..12).find(|slot| cube.edge(*slot).0 as usize == piece).unwrap()
+}
+
+/// The goal also requiring the corners and edges in the given slots solved.
+pub(super) fn with_pieces(goal: MaskedModel, (corners, edges): (&[usize], &[usize])) -> MaskedModel {
+    let goal = corners.iter().fold(goal, |goal, slot| goal.with_corner(*slot, Constraint::Exact));
+    edges.iter().fold(goal, |goal, slot| goal.with_edge(*slot, Constraint::Exact))
+}
+
+/// The cube after the algorithm.
+pub(super) fn applied(cube: &PackedCube, algorithm: &Algorithm) -> PackedCube {
+    let mut cube = *cube;
+    algorithm.apply_to(&mut cube);
+    cube
+}
+
+fn same_centers(a: &PackedCube, b: &PackedCube) -> bool {
+    (0..6).all(|slot| a.center(slot) == b.center(slot))
+}
+
+/// The fewest rotations, at most two, after which the cube satisfies `accept`.
+fn rotation(cube: &PackedCube, accept: impl Fn(&PackedCube) -> bool) -> Algorithm {
+    let singles = ROTATIONS.iter().map(|rotation| vec![*rotation]);
+    let pairs = ROTATIONS.iter().flat_map(|first| ROTATIONS.iter().map(|second| vec![*first, *second]));
+    std::iter::once(vec![])
+        .chain(singles)
+        .chain(pairs)
+        .map(Algorithm::new)
+        .find(|rotation| accept(&applied(cube, rotation)))
+        .unwrap()
+}
+
+/// The shortest sequence of the candidate algorithms, at most `max_depth` of them, bringing the
+/// cube to a state matching `goal`, by breadth first search.
+pub(super) fn shortest(cube: &PackedCube, candidates: &[Algorithm], goal: &MaskedModel, max_depth: usize) -> Option<Vec<Algorithm>> {
+    let mut queue = VecDeque::from([(*cube, Vec::new())]);
+    let mut reached = HashSet::from([*cube]);
+    while let Some((cube, path)) = queue.pop_front() {
+        if goal.matches_packed(&cube) {
+            return Some(path.into_iter().map(|i: usize| candidates[i].clone()).collect());
+        }
+        if path.len() == max_depth {
+            continue;
+        }
+        for (i, candidate) in candidates.iter().enumerate() {
+            let next = applied(&cube, candidate);
+            if reached.insert(next) {
+                let mut next_path = path.clone();
+                next_path.push(i);
+                queue.push_back((next, next_path));
+            }
+        }
+    }
+    None
+}
+
+/// The cube relabeled so that the solved cube is the one matching its centers, the pieces being
+/// named after the slots they solve whatever way the cube is held.
+pub(super) fn relative_to_centers(cube: &PackedCube) -> PackedCube {
+    let solved = applied(&PackedCube::SOLVED, &rotation(&PackedCube::SOLVED, |solved| same_centers(solved, cube)));
+    solved.inverse().multiply(cube)
+}
+
+/// The largest distance of several pattern databases, all tracking pieces the goal of the
+/// search requires solved.
+pub(super) struct Guide<'a>(pub(super) Vec<&'a PatternDatabase>);
+
+impl ModelHeuristic for Guide<'_> {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        self.estimate_packed(&PackedCube::try_from(model).expect("Invalid model"))
+    }
+
+    fn estimate_packed(&self, cube: &PackedCube) -> usize {
+        self.0.iter().map(|database| database.distance(cube)).max().unwrap_or(0)
+    }
+}
+
+/// A cube being solved by a human method, stage after stage.
+/// The cube is first rotated so that a chosen center is down, then relabeled with
+/// `relative_to_centers`, so that the stages are written for the usual orientation.
+pub(super) struct Progress {
+    cube: PackedCube,
+    stage: Vec<Actions>,
+    solution: StagedSolution,
+}
+
+impl Progress {
+    /// Starts solving the model holding the center of the `down` color on the D face. The
+    /// rotation is the first move of the first stage. None if the model cannot be solved.
+    pub(super) fn new(model: &CubeModel, down: TileColor) -> Option<Self> {
+        if !model.is_solvable() {
+            return None;
+        }
+        let cube = PackedCube::try_from(model).ok()?;
+        let center = CENTER_SLOTS.iter().position(|(_, faces)| faces[0].color() == down)? as u8;
+        let hold = rotation(&cube, |cube| cube.center(1) == center);
+        Some(Self {
+            cube: relative_to_centers(&applied(&cube, &hold)),
+            stage: hold.actions().to_vec(),
+            solution: StagedSolution::new(),
+        })
+    }
+
+    pub(super) fn cube(&self) -> &PackedCube {
+        &self.cube
+    }
+
+    pub(super) fn apply(&mut self, algorithm: &Algorithm) {
+        algorithm.apply_to(&mut self.cube);
+        self.stage.extend_from_slice(algorithm.actions());
+    }
+
+    /// Applies the first candidate that brings the cube to a state matching `goal`.
+    pub(super) fn apply_first(&mut self, candidates: impl IntoIterator<Item = Algorithm>, goal: &MaskedModel) -> Option<()> {
+        let algorithm = candidates
+            .into_iter()
+            .find(|candidate| goal.matches_packed(&applied(&self.cube, candidate)))?;
+        self.apply(&algorithm);
+        Some(())
+    }
+
+    /// Applies the moves found by `IdaStar::solve_masked_guided`.
+    pub(super) fn apply_search<H: ModelHeuristic>(&mut self, solver: &IdaStar<H>, goal: &MaskedModel) -> Option<()> {
+        let actions = solver.solve_masked_guided(&CubeModel::from(self.cube), goal)?;
+        self.apply(&Algorithm::new(actions));
+        Some(())
+    }
+
+    /// Applies the shortest sequence of candidates reaching `goal`, see `shortest`.
+    pub(super) fn apply_shortest(&mut self, candidates: &[Algorithm], goal: &MaskedModel, max_depth: usize) -> Option<()> {
+        for algorithm in shortest(&self.cube, candidates, goal, max_depth)? {
+            self.apply(&algorithm);
+        }
+        Some(())
+    }
+
+    /// Solves the last layer of a cube held with its first two layers solved, by an OLL then a
+    /// PLL stage named as by `Cfop::solve`, the PLL ending with the last U turn.
+    pub(super) fn last_layer(&mut self) -> Option<()> {
+        let (name, algorithm) = match orientation_case(&self.cube) {
+            Some((case, algorithm)) => (case.name.to_string(), algorithm),
+            None => ("OLL skip".to_string(), Algorithm::default()),
+        };
+        self.apply(&algorithm);
+        self.end_stage(&name);
+
+        let (name, algorithm) = match permutation_case(&self.cube) {
+            Some((case, algorithm)) => (format!("PLL {}", case.name), algorithm),
+            None => ("PLL skip".to_string(), Algorithm::default()),
+        };
+        self.apply(&algorithm);
+        let solved = MaskedModel::solved().with_all(Constraint::Exact);
+        self.apply_first((0..4).map(up_turn), &solved)?;
+        self.end_stage(&name);
+        Some(())
+    }
+
+    /// Closes the current stage, its moves simplified.
+    pub(super) fn end_stage(&mut self, name: &str) {
+        let stage = Algorithm::new(std::mem::take(&mut self.stage)).simplified();
+        self.solution.push_stage(name, stage);
+    }
+
+    /// The stages, once the cube is solved.
+    pub(super) fn finish(self) -> Option<StagedSolution> {
+        self.cube.is_solved().then_some(self.solution)
+    }
+}
+
+/// Helpers shared by the tests of the methods.
+#[cfg(test)]
+pub(super) mod testing {
+    use super::*;
+    use rand::{seq::SliceRandom, Rng};
+
+    /// The solved model after the scramble.
+    pub(crate) fn scrambled(scramble: &str) -> CubeModel {
+        let mut model = CubeModel::solved();
+        alg(scramble).apply_to(&mut model);
+        model
+    }
+
+    /// The solved model after `length` random face turns.
+    pub(crate) fn random_scramble(rng: &mut impl Rng, length: usize) -> CubeModel {
+        let mut model = CubeModel::solved();
+        Algorithm::new((0..length).map(|_| *Actions::FACE_TURNS.choose(rng).unwrap()).collect()).apply_to(&mut model);
+        model
+    }
+
+    /// Panics unless the solution solves the model, whatever way its centers are turned.
+    pub(crate) fn assert_solves(solution: &StagedSolution, model: &CubeModel) {
+        let mut model = model.clone();
+        solution.algorithm().apply_to(&mut model);
+        let cube = PackedCube::try_from(&model).unwrap();
+        assert!(relative_to_centers(&cube).is_solved(), "{}", solution);
+    }
+}
@@ -1,7 +1,31 @@
 mod action;
+mod algorithm;
 mod algos;
+mod cfop;
+mod evaluation;
 mod heuristic;
+mod layer_by_layer;
+mod method;
+mod pattern_database;
+mod roux;
+mod search;
 mod state_space;
+mod thistlethwaite;
+mod two_phase;
+mod zz;
 
 pub use state_space::{CubeState, StateSpace};
+pub use search::{CancellationToken, Limit, SearchLimits, SearchResult, SearchStats};
 pub use action::{Actions, Actionable};
+pub use algorithm::{Algorithm, NotationError, StagedSolution};
+pub use algos::IdaStar;
+pub use heuristic::{HeuristicRegistry, ModelHeuristic, RelativeHeuristic, SharedHeuristic};
+pub use heuristic::{ColorDisparity, ColorDistance, MisplacedCubies, MisplacedTiles, MoveDistance};
+pub use evaluation::{evaluate_heuristics, HeuristicReport};
+pub use cfop::{Cfop, LastLayerCase};
+pub use layer_by_layer::LayerByLayer;
+pub use pattern_database::{Pattern, PatternDatabase, PatternDatabases, PdbError};
+pub use roux::Roux;
+pub use thistlethwaite::Thistlethwaite;
+pub use two_phase::TwoPhase;
+pub use zz::Zz;
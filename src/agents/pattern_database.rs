@@ -0,0 +1,686 @@
+use super::action::Actions;
+use super::heuristic::ModelHeuristic;
+use crate::core::{CubeModel, PackedCube};
+use std::{
+    error::Error,
+    fmt::Display,
+    fs,
+    io,
+    path::Path,
+};
+
+const MAGIC: &[u8; 8] = b"RUCUSPDB";
+const VERSION: u16 = 1;
+/// Distances are stored in 4 bits, the highest value marks entries not reached yet.
+const UNKNOWN: u8 = 0xF;
+
+/// Reasons why pattern databases could not be read.
+#[derive(Debug)]
+pub enum PdbError {
+    Io(io::Error),
+    /// The file does not start with the pattern database magic bytes.
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// The content does not match the checksum at the end of the file.
+    ChecksumMismatch,
+    /// The content is well formed but does not describe valid databases.
+    Corrupted(&'static str),
+    /// A pattern tracks the same corner or edge more than once, by the index of its home slot.
+    DuplicatePiece { corner: bool, piece: u8 },
+}
+
+impl Display for PdbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PdbError::Io(err) => write!(f, "cannot access pattern databases: {}", err),
+            PdbError::BadMagic => f.write_str("not a pattern database file"),
+            PdbError::UnsupportedVersion(version) => {
+                write!(f, "unsupported pattern database version {}", version)
+            }
+            PdbError::ChecksumMismatch => f.write_str("pattern database checksum mismatch"),
+            PdbError::Corrupted(reason) => write!(f, "corrupted pattern database: {}", reason),
+            PdbError::DuplicatePiece { corner, piece } => {
+                let kind = if *corner { "corner" } else { "edge" };
+                write!(f, "corrupted pattern database: {} {} tracked twice", kind, piece)
+            }
+        }
+    }
+}
+
+impl Error for PdbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PdbError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PdbError {
+    fn from(err: io::Error) -> Self {
+        PdbError::Io(err)
+    }
+}
+
+/// 64 bit FNV-1a hash, used as checksum of the database files.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Slot and orientation of every corner and edge, indexed by the slot they occupy when solved,
+/// in the order of `CORNER_SLOTS` and `EDGE_SLOTS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pieces {
+    corner_slots: [u8; 8],
+    corner_orientations: [u8; 8],
+    edge_slots: [u8; 12],
+    edge_orientations: [u8; 12],
+}
+
+impl Pieces {
+    fn solved() -> Self {
+        Self {
+            corner_slots: [0, 1, 2, 3, 4, 5, 6, 7],
+            corner_orientations: [0; 8],
+            edge_slots: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+            edge_orientations: [0; 12],
+        }
+    }
+
+    fn from_packed(cube: &PackedCube) -> Self {
+        let mut pieces = Self::solved();
+        for slot in 0..8 {
+            let (piece, orientation) = cube.corner(slot);
+            pieces.corner_slots[piece as usize] = slot as u8;
+            pieces.corner_orientations[piece as usize] = orientation;
+        }
+        for slot in 0..12 {
+            let (piece, orientation) = cube.edge(slot);
+            pieces.edge_slots[piece as usize] = slot as u8;
+            pieces.edge_orientations[piece as usize] = orientation;
+        }
+        pieces
+    }
+
+    fn apply(&mut self, moves: &PieceMoves) {
+        for piece in 0..8 {
+            let slot = self.corner_slots[piece] as usize;
+            self.corner_slots[piece] = moves.corner_to[slot];
+            self.corner_orientations[piece] = (self.corner_orientations[piece] + moves.corner_twist[slot]) % 3;
+        }
+        for piece in 0..12 {
+            let slot = self.edge_slots[piece] as usize;
+            self.edge_slots[piece] = moves.edge_to[slot];
+            self.edge_orientations[piece] = (self.edge_orientations[piece] + moves.edge_flip[slot]) % 2;
+        }
+    }
+}
+
+/// Where an action sends the piece in each slot, and the orientation it adds to it.
+struct PieceMoves {
+    corner_to: [u8; 8],
+    corner_twist: [u8; 8],
+    edge_to: [u8; 12],
+    edge_flip: [u8; 12],
+}
+
+impl PieceMoves {
+    /// Reads the effect of the action on the solved cube, where every piece starts oriented in its home slot.
+    fn from_action(action: Actions) -> Self {
+        let pieces = Pieces::from_packed(action.packed());
+        Self {
+            corner_to: pieces.corner_slots,
+            corner_twist: pieces.corner_orientations,
+            edge_to: pieces.edge_slots,
+            edge_flip: pieces.edge_orientations,
+        }
+    }
+}
+
+/// Ranks the slots of `k` distinct pieces among `n` slots, in 0..n!/(n-k)!.
+fn rank_slots(slots: &[u8], n: usize) -> usize {
+    let mut used = 0u16;
+    let mut rank = 0;
+    for (i, slot) in slots.iter().enumerate() {
+        let smaller_unused = (0..*slot).filter(|s| used & (1 << s) == 0).count();
+        rank = rank * (n - i) + smaller_unused;
+        used |= 1 << slot;
+    }
+    rank
+}
+
+fn unrank_slots(mut rank: usize, n: usize, slots: &mut [u8]) {
+    let k = slots.len();
+    let mut digits = [0; 12];
+    for i in (0..k).rev() {
+        digits[i] = rank % (n - i);
+        rank /= n - i;
+    }
+    let mut used = 0u16;
+    for i in 0..k {
+        let slot = (0..n as u8)
+            .filter(|s| used & (1 << s) == 0)
+            .nth(digits[i])
+            .unwrap();
+        slots[i] = slot;
+        used |= 1 << slot;
+    }
+}
+
+/// The first value found again later in `values`.
+fn first_duplicate<T: PartialEq + Copy>(values: &[T]) -> Option<T> {
+    values.iter().enumerate().find(|(i, value)| values[i + 1..].contains(value)).map(|(_, value)| *value)
+}
+
+/// The corners and edges tracked by a pattern database, by the index of their home slot in
+/// `CORNER_SLOTS` and `EDGE_SLOTS`. The others are ignored, so the database stores the moves
+/// needed to solve the tracked pieces only, which never exceeds the moves to solve the cube.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    corners: Vec<u8>,
+    edges: Vec<u8>,
+}
+
+impl Pattern {
+    pub fn new(corners: &[usize], edges: &[usize]) -> Self {
+        assert!(corners.iter().all(|c| *c < 8), "There are only 8 corners");
+        assert!(edges.iter().all(|e| *e < 12), "There are only 12 edges");
+        if let Some(corner) = first_duplicate(corners) {
+            panic!("Corner {} is tracked twice", corner);
+        }
+        if let Some(edge) = first_duplicate(edges) {
+            panic!("Edge {} is tracked twice", edge);
+        }
+        Self {
+            corners: corners.iter().map(|c| *c as u8).collect(),
+            edges: edges.iter().map(|e| *e as u8).collect(),
+        }
+    }
+
+    /// All the corners, the first database of Korf.
+    pub fn corners() -> Self {
+        Self::new(&[0, 1, 2, 3, 4, 5, 6, 7], &[])
+    }
+
+    /// Half of the edges, Korf uses one database for each half.
+    pub fn edges(first_half: bool) -> Self {
+        if first_half {
+            Self::new(&[], &[0, 1, 2, 3, 4, 5])
+        } else {
+            Self::new(&[], &[6, 7, 8, 9, 10, 11])
+        }
+    }
+
+    fn slot_count(pieces: usize, total: usize) -> usize {
+        (total - pieces + 1..=total).product()
+    }
+
+    /// When all the pieces of a kind are tracked the orientation of the last one is implied.
+    fn orientation_count(pieces: usize, total: usize, orientations: usize) -> usize {
+        let free = if pieces == total { pieces - 1 } else { pieces };
+        orientations.pow(free as u32)
+    }
+
+    fn corner_slot_count(&self) -> usize {
+        Self::slot_count(self.corners.len(), 8)
+    }
+
+    fn corner_orientation_count(&self) -> usize {
+        Self::orientation_count(self.corners.len(), 8, 3)
+    }
+
+    fn edge_slot_count(&self) -> usize {
+        Self::slot_count(self.edges.len(), 12)
+    }
+
+    fn edge_orientation_count(&self) -> usize {
+        Self::orientation_count(self.edges.len(), 12, 2)
+    }
+
+    /// Number of entries of the database.
+    pub fn size(&self) -> usize {
+        self.checked_size().expect("The pattern has more entries than fit in memory")
+    }
+
+    /// Number of entries of the database, None when it overflows, e.g. with all the pieces.
+    fn checked_size(&self) -> Option<usize> {
+        self.corner_slot_count()
+            .checked_mul(self.corner_orientation_count())?
+            .checked_mul(self.edge_slot_count())?
+            .checked_mul(self.edge_orientation_count())
+    }
+
+    fn rank(&self, pieces: &Pieces) -> usize {
+        let mut slots = [0; 12];
+        let mut orientation = 0;
+
+        for (i, corner) in self.corners.iter().enumerate() {
+            slots[i] = pieces.corner_slots[*corner as usize];
+        }
+        let corner_slots = rank_slots(&slots[..self.corners.len()], 8);
+        for corner in self.corners.iter().take(self.corner_orientation_count().ilog(3) as usize) {
+            orientation = orientation * 3 + pieces.corner_orientations[*corner as usize] as usize;
+        }
+        let mut rank = corner_slots * self.corner_orientation_count() + orientation;
+
+        for (i, edge) in self.edges.iter().enumerate() {
+            slots[i] = pieces.edge_slots[*edge as usize];
+        }
+        let edge_slots = rank_slots(&slots[..self.edges.len()], 12);
+        orientation = 0;
+        for edge in self.edges.iter().take(self.edge_orientation_count().ilog2() as usize) {
+            orientation = orientation * 2 + pieces.edge_orientations[*edge as usize] as usize;
+        }
+        rank = rank * self.edge_slot_count() + edge_slots;
+        rank * self.edge_orientation_count() + orientation
+    }
+
+    /// Rebuilds the tracked pieces of a rank, the others are left solved.
+    fn unrank(&self, rank: usize) -> Pieces {
+        let mut pieces = Pieces::solved();
+        let mut slots = [0; 12];
+
+        let mut orientation = rank % self.edge_orientation_count();
+        let rank = rank / self.edge_orientation_count();
+        unrank_slots(rank % self.edge_slot_count(), 12, &mut slots[..self.edges.len()]);
+        let rank = rank / self.edge_slot_count();
+        let free = self.edge_orientation_count().ilog2() as usize;
+        let mut flip = 0;
+        for (i, edge) in self.edges.iter().enumerate().rev() {
+            pieces.edge_slots[*edge as usize] = slots[i];
+            let edge_orientation = if i < free {
+                let o = (orientation % 2) as u8;
+                orientation /= 2;
+                o
+            } else {
+                // the last edge of a full set
+                0
+            };
+            pieces.edge_orientations[*edge as usize] = edge_orientation;
+            flip += edge_orientation;
+        }
+        if free < self.edges.len() {
+            let last = *self.edges.last().unwrap() as usize;
+            pieces.edge_orientations[last] = flip % 2;
+        }
+
+        orientation = rank % self.corner_orientation_count();
+        let rank = rank / self.corner_orientation_count();
+        unrank_slots(rank, 8, &mut slots[..self.corners.len()]);
+        let free = self.corner_orientation_count().ilog(3) as usize;
+        let mut twist = 0;
+        for (i, corner) in self.corners.iter().enumerate().rev() {
+            pieces.corner_slots[*corner as usize] = slots[i];
+            let corner_orientation = if i < free {
+                let o = (orientation % 3) as u8;
+                orientation /= 3;
+                o
+            } else {
+                0
+            };
+            pieces.corner_orientations[*corner as usize] = corner_orientation;
+            twist += corner_orientation;
+        }
+        if free < self.corners.len() {
+            let last = *self.corners.last().unwrap() as usize;
+            pieces.corner_orientations[last] = (3 - twist % 3) % 3;
+        }
+        pieces
+    }
+}
+
+/// Exact number of face turns needed to solve the pieces of a pattern, for every arrangement
+/// of them, found by a breadth first search from the solved cube.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternDatabase {
+    pattern: Pattern,
+    /// Two 4 bits distances per byte, the lower bits hold the even entries.
+    distances: Vec<u8>,
+}
+
+impl PatternDatabase {
+    /// Panics if some arrangement of the pieces needs 15 moves or more, which does not fit in
+    /// the 4 bits of an entry.
+    pub fn generate(pattern: Pattern) -> Self {
+        let size = pattern.size();
+        let mut database = Self {
+            distances: vec![0xFF; size.div_ceil(2)],
+            pattern,
+        };
+        let moves = Actions::FACE_TURNS.map(PieceMoves::from_action);
+        let solved = database.pattern.rank(&Pieces::solved());
+        database.set(solved, 0);
+
+        let mut depth = 0;
+        let mut reached = 1;
+        while reached > 0 {
+            reached = 0;
+            for rank in 0..size {
+                if database.get(rank) != depth {
+                    continue;
+                }
+                let pieces = database.pattern.unrank(rank);
+                for piece_moves in moves.iter() {
+                    let mut next = pieces;
+                    next.apply(piece_moves);
+                    let next_rank = database.pattern.rank(&next);
+                    if database.get(next_rank) == UNKNOWN {
+                        // the entries hold 4 bits, the largest value marks the unknown ones
+                        assert!(depth + 1 < UNKNOWN, "Distances of 15 moves or more do not fit in a database entry");
+                        database.set(next_rank, depth + 1);
+                        reached += 1;
+                    }
+                }
+            }
+            depth += 1;
+        }
+        database
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    fn get(&self, rank: usize) -> u8 {
+        (self.distances[rank / 2] >> ((rank % 2) * 4)) & 0xF
+    }
+
+    fn set(&mut self, rank: usize, distance: u8) {
+        let shift = (rank % 2) * 4;
+        let byte = &mut self.distances[rank / 2];
+        *byte = (*byte & !(0xF << shift)) | (distance << shift);
+    }
+
+    /// Moves needed to solve the pieces of the pattern in `cube`.
+    pub fn distance(&self, cube: &PackedCube) -> usize {
+        self.get(self.pattern.rank(&Pieces::from_packed(cube))) as usize
+    }
+}
+
+impl ModelHeuristic for PatternDatabase {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        self.estimate_packed(&PackedCube::try_from(model).expect("Invalid model"))
+    }
+
+    fn estimate_packed(&self, cube: &PackedCube) -> usize {
+        self.distance(cube)
+    }
+}
+
+/// A set of pattern databases used together as an admissible heuristic, the largest of their
+/// distances. They are saved to a single file laid out as follows, integers in little endian:
+/// - the magic bytes `RUCUSPDB` and the version as u16
+/// - the number of databases as u8
+/// - for each database the number of corners as u8 followed by their indexes, the same for the
+///   edges, the number of entries as u64 and the distances, two per byte
+/// - the FNV-1a checksum of all the previous bytes as u64
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternDatabases {
+    databases: Vec<PatternDatabase>,
+}
+
+impl PatternDatabases {
+    pub fn generate(patterns: Vec<Pattern>) -> Self {
+        Self {
+            databases: patterns.into_iter().map(PatternDatabase::generate).collect(),
+        }
+    }
+
+    /// The corner database and the two edge databases of Korf.
+    pub fn korf() -> Self {
+        Self::generate(vec![Pattern::corners(), Pattern::edges(true), Pattern::edges(false)])
+    }
+
+    /// Loads the Korf databases from `path`, generating and saving them there if the file does not exist.
+    pub fn load_or_generate_korf(path: &Path) -> Result<Self, PdbError> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            let databases = Self::korf();
+            databases.save(path)?;
+            Ok(databases)
+        }
+    }
+
+    pub fn databases(&self) -> &[PatternDatabase] {
+        &self.databases
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.push(self.databases.len() as u8);
+        for database in self.databases.iter() {
+            bytes.push(database.pattern.corners.len() as u8);
+            bytes.extend_from_slice(&database.pattern.corners);
+            bytes.push(database.pattern.edges.len() as u8);
+            bytes.extend_from_slice(&database.pattern.edges);
+            bytes.extend_from_slice(&(database.pattern.size() as u64).to_le_bytes());
+            bytes.extend_from_slice(&database.distances);
+        }
+        let checksum = fnv1a(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PdbError> {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(PdbError::BadMagic);
+        }
+        if bytes.len() < MAGIC.len() + 2 + 8 {
+            return Err(PdbError::Corrupted("file too short"));
+        }
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if version != VERSION {
+            return Err(PdbError::UnsupportedVersion(version));
+        }
+        let (content, checksum) = bytes.split_at(bytes.len() - 8);
+        if fnv1a(content) != u64::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(PdbError::ChecksumMismatch);
+        }
+
+        let mut reader = ByteReader {
+            bytes: content,
+            position: MAGIC.len() + 2,
+        };
+        let count = reader.read(1)?[0];
+        let mut databases = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let corner_count = reader.read(1)?[0] as usize;
+            let corners = reader.read(corner_count)?.to_vec();
+            let edge_count = reader.read(1)?[0] as usize;
+            let edges = reader.read(edge_count)?.to_vec();
+            if corner_count > 8 || edge_count > 12 || corners.iter().any(|c| *c >= 8) || edges.iter().any(|e| *e >= 12) {
+                return Err(PdbError::Corrupted("invalid pattern"));
+            }
+            if let Some(piece) = first_duplicate(&corners) {
+                return Err(PdbError::DuplicatePiece { corner: true, piece });
+            }
+            if let Some(piece) = first_duplicate(&edges) {
+                return Err(PdbError::DuplicatePiece { corner: false, piece });
+            }
+            let pattern = Pattern { corners, edges };
+            let size = u64::from_le_bytes(reader.read(8)?.try_into().unwrap()) as usize;
+            let Some(pattern_size) = pattern.checked_size() else {
+                return Err(PdbError::Corrupted("pattern too large"));
+            };
+            if size != pattern_size {
+                return Err(PdbError::Corrupted("size does not match the pattern"));
+            }
+            let distances = reader.read(size.div_ceil(2))?.to_vec();
+            databases.push(PatternDatabase { pattern, distances });
+        }
+        if reader.position != content.len() {
+            return Err(PdbError::Corrupted("unexpected bytes after the databases"));
+        }
+        Ok(Self { databases })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PdbError> {
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PdbError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+impl ModelHeuristic for PatternDatabases {
+    fn estimate(&self, model: &CubeModel) -> usize {
+        self.estimate_packed(&PackedCube::try_from(model).expect("Invalid model"))
+    }
+
+    fn estimate_packed(&self, cube: &PackedCube) -> usize {
+        let pieces = Pieces::from_packed(cube);
+        self.databases
+            .iter()
+            .map(|database| database.get(database.pattern.rank(&pieces)) as usize)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn read(&mut self, length: usize) -> Result<&'a [u8], PdbError> {
+        let end = self.position + length;
+        if end > self.bytes.len() {
+            return Err(PdbError::Corrupted("unexpected end of file"));
+        }
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::{Actionable, Algorithm, IdaStar, MisplacedCubies};
+
+    fn small_databases() -> PatternDatabases {
+        PatternDatabases::generate(vec![
+            Pattern::new(&[0, 1, 2], &[]),
+            Pattern::new(&[], &[0, 1, 2]),
+            Pattern::new(&[4, 5], &[8]),
+        ])
+    }
+
+    fn scrambled(scramble: &str) -> CubeModel {
+        let mut model = CubeModel::solved();
+        scramble.parse::<Algorithm>().unwrap().apply_to(&mut model);
+        model
+    }
+
+    #[test]
+    fn rank_and_unrank_are_inverse() {
+        let model = scrambled("R U F' L2 D B' R2 U'");
+        for pattern in [
+            Pattern::new(&[0, 1, 2, 3, 4, 5, 6, 7], &[]),
+            Pattern::new(&[], &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+            Pattern::new(&[2, 6], &[1, 5, 11]),
+        ] {
+            let rank = pattern.rank(&Pieces::from_packed(&PackedCube::try_from(&model).unwrap()));
+            assert!(rank < pattern.size());
+            assert_eq!(pattern.rank(&pattern.unrank(rank)), rank);
+        }
+    }
+
+    #[test]
+    fn piece_moves_match_the_model() {
+        for action in Actions::FACE_TURNS {
+            let mut cube = PackedCube::try_from(&scrambled("R U F' L2 D B'")).unwrap();
+            let mut pieces = Pieces::from_packed(&cube);
+            cube.apply_action(action);
+            pieces.apply(&PieceMoves::from_action(action));
+            assert_eq!(pieces, Pieces::from_packed(&cube), "{}", action);
+        }
+    }
+
+    #[test]
+    fn distances_of_short_scrambles() {
+        let databases = small_databases();
+        assert_eq!(databases.estimate(&CubeModel::solved()), 0);
+        assert_eq!(databases.estimate(&scrambled("U")), 1);
+        for scramble in ["R U", "F' L D", "R U R' U'"] {
+            let model = scrambled(scramble);
+            let optimal = IdaStar::new(MisplacedCubies).solve(&model).unwrap().len();
+            assert!(databases.estimate(&model) <= optimal, "{}", scramble);
+        }
+    }
+
+    #[test]
+    fn ida_star_with_pattern_databases() {
+        let solver = IdaStar::new(small_databases());
+        let mut model = scrambled("R U2 F' L D");
+        let solution = solver.solve(&model).unwrap();
+        assert_eq!(solution.len(), 5);
+        Algorithm::from(solution).apply_to(&mut model);
+        assert!(model.is_solved());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let databases = small_databases();
+        let path = std::env::temp_dir().join(format!("rucus-pdb-{}.bin", std::process::id()));
+        databases.save(&path).unwrap();
+        let loaded = PatternDatabases::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, databases);
+    }
+
+    #[test]
+    fn rejects_damaged_files() {
+        let bytes = small_databases().to_bytes();
+
+        let mut corrupted = bytes.clone();
+        corrupted[20] ^= 1;
+        assert!(matches!(PatternDatabases::from_bytes(&corrupted), Err(PdbError::ChecksumMismatch)));
+
+        let mut other_version = bytes.clone();
+        other_version[8] = 2;
+        assert!(matches!(
+            PatternDatabases::from_bytes(&other_version),
+            Err(PdbError::UnsupportedVersion(2))
+        ));
+
+        assert!(matches!(PatternDatabases::from_bytes(b"not a database"), Err(PdbError::BadMagic)));
+
+        // the first pattern tracks corner 0 twice, the checksum being computed again
+        let mut duplicate = bytes[..bytes.len() - 8].to_vec();
+        assert_eq!(duplicate[11..15], [3, 0, 1, 2]);
+        duplicate[13] = 0;
+        let checksum = fnv1a(&duplicate);
+        duplicate.extend_from_slice(&checksum.to_le_bytes());
+        assert!(matches!(
+            PatternDatabases::from_bytes(&duplicate),
+            Err(PdbError::DuplicatePiece { corner: true, piece: 0 })
+        ));
+    }
+
+    #[test]
+    fn rejects_patterns_too_large() {
+        // all the pieces, the number of entries overflows
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&[1, 8, 0, 1, 2, 3, 4, 5, 6, 7, 12, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        let checksum = fnv1a(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        assert!(matches!(PatternDatabases::from_bytes(&bytes), Err(PdbError::Corrupted("pattern too large"))));
+    }
+
+    #[test]
+    #[should_panic(expected = "Edge 3 is tracked twice")]
+    fn patterns_track_pieces_once() {
+        Pattern::new(&[0], &[3, 1, 3]);
+    }
+}
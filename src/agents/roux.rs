@@ -0,0 +1,152 @@
+use super::action::Actions;
+use super::algorithm::{Algorithm, StagedSolution};
+use super::algos::IdaStar;
+use super::cfop::{OLL, PLL};
+use super::method::{after_up, alg, joined, up_turn, with_pieces, Guide, Progress};
+use super::pattern_database::{Pattern, PatternDatabase};
+use crate::core::{Constraint, CubeModel, Face, MaskedModel, TileColor};
+
+/// Slots of the corners and edges of the first block, the 1x2x3 block on the left, and of the
+/// second block, on the right, built as a square at the back then a pair at the front.
+const FIRST_BLOCK: (&[usize], &[usize]) = (&[5, 6], &[6, 9, 10]);
+const SECOND_SQUARE: (&[usize], &[usize]) = (&[7], &[4, 11]);
+const SECOND_PAIR: (&[usize], &[usize]) = (&[4], &[8]);
+
+/// The moves building the second block without breaking the first one, r amounts to R and M.
+const SECOND_BLOCK_ACTIONS: [Actions; 9] = [
+    Actions::R,
+    Actions::RC,
+    Actions::R2,
+    Actions::U,
+    Actions::UC,
+    Actions::U2,
+    Actions::M,
+    Actions::MC,
+    Actions::M2,
+];
+
+/// The moves of the last six edges.
+const LSE_ACTIONS: [Actions; 6] = [Actions::M, Actions::MC, Actions::M2, Actions::U, Actions::UC, Actions::U2];
+
+/// The corners of the last layer in two looks, taken from the tables of `Cfop`: OLL 21 to 27,
+/// the cases with the edges already oriented, which only orient the corners, then the T and Y
+/// PLLs, swapping two adjacent or two diagonal corners. The edges they move are left to the
+/// last six edges.
+const CORNER_ORIENTATION: std::ops::Range<usize> = 20..27;
+const CORNER_PERMUTATION: [&str; 2] = ["T", "Y"];
+
+/// Solves the cube with the Roux method, holding white down: a 1x2x3 block on the left and one
+/// on the right, both by searches guided by pattern databases of their pieces, the second one
+/// turning R, U and M only, then the corners of the last layer in two looks, an OLL then a PLL
+/// of the corners standing in for CMLL, and the last six edges with M and U turns, in three
+/// optimal steps: their orientation, the UL and UR edges, then the M slice.
+pub struct Roux {
+    /// Databases of the corners with the bottom edge, of the three edges and of each corner
+    /// with the middle edge next to it.
+    first_block: Vec<PatternDatabase>,
+    second_square: PatternDatabase,
+    second_pair: PatternDatabase,
+}
+
+impl Roux {
+    /// Generates the pattern databases guiding the block searches.
+    pub fn new() -> Self {
+        let first_block = [
+            Pattern::new(&[5, 6], &[6]),
+            Pattern::new(&[], &[6, 9, 10]),
+            Pattern::new(&[5], &[9]),
+            Pattern::new(&[6], &[10]),
+        ];
+        Self {
+            first_block: first_block.into_iter().map(PatternDatabase::generate).collect(),
+            second_square: PatternDatabase::generate(Pattern::new(SECOND_SQUARE.0, SECOND_SQUARE.1)),
+            second_pair: PatternDatabase::generate(Pattern::new(SECOND_PAIR.0, SECOND_PAIR.1)),
+        }
+    }
+
+    /// The stages `First block`, `Second block`, `CMLL`, `LSE orientation`, `LSE UL and UR` and
+    /// `LSE M slice`, the first block starting with the rotation holding white down. None if the
+    /// model cannot be solved.
+    pub fn solve(&self, model: &CubeModel) -> Option<StagedSolution> {
+        let mut progress = Progress::new(model, TileColor::White)?;
+
+        let goal = with_pieces(MaskedModel::solved(), FIRST_BLOCK);
+        let solver = IdaStar::new(Guide(self.first_block.iter().collect()));
+        progress.apply_search(&solver, &goal)?;
+        progress.end_stage("First block");
+
+        let goal = with_pieces(goal, SECOND_SQUARE);
+        let solver = IdaStar::new(Guide(vec![&self.second_square])).with_actions(&SECOND_BLOCK_ACTIONS);
+        progress.apply_search(&solver, &goal)?;
+        let goal = with_pieces(goal, SECOND_PAIR);
+        let solver = IdaStar::new(Guide(vec![&self.second_square, &self.second_pair])).with_actions(&SECOND_BLOCK_ACTIONS);
+        progress.apply_search(&solver, &goal)?;
+        progress.end_stage("Second block");
+
+        let goal = goal.with_corners_on(Face::Up, Constraint::Orientation);
+        let cases = std::iter::once(Algorithm::default()).chain(OLL[CORNER_ORIENTATION].iter().map(|(_, notation)| alg(notation)));
+        progress.apply_first(cases.flat_map(|case| (0..4).map(move |q| after_up(q, &case))), &goal)?;
+        let goal = goal.with_corners_on(Face::Up, Constraint::Exact);
+        let swaps = PLL.iter().filter(|(name, _)| CORNER_PERMUTATION.contains(name));
+        let cases: Vec<_> = std::iter::once(Algorithm::default()).chain(swaps.map(|(_, notation)| alg(notation))).collect();
+        let cases = cases.iter().flat_map(|case| (0..4).flat_map(move |q| (0..4).map(move |p| joined(&after_up(q, case), &up_turn(p)))));
+        progress.apply_first(cases, &goal)?;
+        progress.end_stage("CMLL");
+
+        let moves = LSE_ACTIONS.map(|action| Algorithm::new(vec![action]));
+        let goal = goal.with_all(Constraint::Orientation).with_corners_on(Face::Up, Constraint::Exact);
+        let goal = with_pieces(with_pieces(with_pieces(goal, FIRST_BLOCK), SECOND_SQUARE), SECOND_PAIR);
+        progress.apply_shortest(&moves, &goal, 20)?;
+        progress.end_stage("LSE orientation");
+
+        let goal = goal.with_edge(0, Constraint::Exact).with_edge(2, Constraint::Exact);
+        progress.apply_shortest(&moves, &goal, 20)?;
+        progress.end_stage("LSE UL and UR");
+
+        progress.apply_shortest(&moves, &goal.with_all(Constraint::Exact), 20)?;
+        progress.end_stage("LSE M slice");
+
+        progress.finish()
+    }
+}
+
+impl Default for Roux {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::method::testing::{assert_solves, random_scramble};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::OnceLock;
+
+    fn solver() -> &'static Roux {
+        static SOLVER: OnceLock<Roux> = OnceLock::new();
+        SOLVER.get_or_init(Roux::new)
+    }
+
+    #[test]
+    fn solved_cube() {
+        // only the rotation holding white down
+        let solution = solver().solve(&CubeModel::solved()).unwrap();
+        assert_eq!(solution.len(), 1);
+        assert_eq!(solution.stages().len(), 6);
+    }
+
+    #[test]
+    fn solves_scrambles() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..3 {
+            let model = random_scramble(&mut rng, 25);
+            let solution = solver().solve(&model).unwrap();
+            // the last six edges only turn M and U
+            for (_, algorithm) in &solution.stages()[3..] {
+                assert!(algorithm.actions().iter().all(|action| LSE_ACTIONS.contains(action)));
+            }
+            assert_solves(&solution, &model);
+        }
+    }
+}
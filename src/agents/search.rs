@@ -0,0 +1,227 @@
+use super::action::Actions;
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Bounds on the work of a search, none by default.
+/// The memory is an estimate of the bytes held by the search, see `StateSpace`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchLimits {
+    max_nodes: Option<usize>,
+    max_time: Option<Duration>,
+    max_memory: Option<usize>,
+}
+
+impl SearchLimits {
+    /// No limit at all, the search runs until it finds the goal or runs out of states.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Stops after expanding `max_nodes` states.
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Stops once the search has run for `max_time`.
+    pub fn with_max_time(mut self, max_time: Duration) -> Self {
+        self.max_time = Some(max_time);
+        self
+    }
+
+    /// Stops once the search holds about `max_memory` bytes.
+    pub fn with_max_memory(mut self, max_memory: usize) -> Self {
+        self.max_memory = Some(max_memory);
+        self
+    }
+
+    pub fn max_nodes(&self) -> Option<usize> {
+        self.max_nodes
+    }
+
+    pub fn max_time(&self) -> Option<Duration> {
+        self.max_time
+    }
+
+    pub fn max_memory(&self) -> Option<usize> {
+        self.max_memory
+    }
+
+    /// The first limit exceeded by a search that expanded `nodes` states in `elapsed` and holds
+    /// `memory` bytes.
+    pub(super) fn exceeded(&self, nodes: usize, elapsed: Duration, memory: usize) -> Option<Limit> {
+        if self.max_nodes.is_some_and(|max| nodes >= max) {
+            Some(Limit::Nodes)
+        } else if self.max_time.is_some_and(|max| elapsed >= max) {
+            Some(Limit::Time)
+        } else if self.max_memory.is_some_and(|max| memory >= max) {
+            Some(Limit::Memory)
+        } else {
+            None
+        }
+    }
+}
+
+/// Stops a search from another thread. Clones share the same flag, the search keeps one and
+/// the caller cancels through another.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Why a search stopped before reaching its goal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Nodes,
+    Time,
+    Memory,
+    Cancelled,
+}
+
+/// How a search ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchResult {
+    Solved(Vec<Actions>),
+    /// The search was stopped by `limit`, `best_path` leads to the state that looked the closest
+    /// to the goal according to the heuristic.
+    LimitReached { limit: Limit, best_path: Vec<Actions> },
+    /// The goal cannot be reached from the initial state.
+    Unsolvable,
+}
+
+impl SearchResult {
+    pub fn is_solved(&self) -> bool {
+        matches!(self, SearchResult::Solved(_))
+    }
+
+    pub fn solution(&self) -> Option<&[Actions]> {
+        match self {
+            SearchResult::Solved(actions) => Some(actions),
+            _ => None,
+        }
+    }
+
+    pub fn into_solution(self) -> Option<Vec<Actions>> {
+        match self {
+            SearchResult::Solved(actions) => Some(actions),
+            _ => None,
+        }
+    }
+}
+
+/// What a search did, reported to the observer of `StateSpace::solve_with` after every
+/// expansion and returned with the result.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SearchStats {
+    pub nodes_expanded: usize,
+    /// Successors built while expanding, including the duplicates.
+    pub nodes_generated: usize,
+    pub frontier_size: usize,
+    pub max_frontier_size: usize,
+    /// States not pushed to the frontier, or not expanded, as they were already reached through
+    /// a path at least as short.
+    pub duplicates_pruned: usize,
+    pub elapsed: Duration,
+    /// The successive values of the smallest path cost plus estimate in the frontier, each
+    /// recorded when the search first went past the previous one.
+    pub contours: Vec<usize>,
+    /// Length of the solution, once found.
+    pub solution_depth: Option<usize>,
+}
+
+impl SearchStats {
+    /// The branching factor b of a uniform tree as deep as the solution holding as many nodes
+    /// as were generated, that is N + 1 = 1 + b + b^2 + ... + b^d. None until a solution of at
+    /// least one move is found.
+    pub fn effective_branching_factor(&self) -> Option<f64> {
+        let depth = self.solution_depth.filter(|depth| *depth > 0)? as i32;
+        let nodes = self.nodes_generated as f64;
+        let tree_size = |b: f64| (1..=depth).map(|d| b.powi(d)).sum::<f64>();
+        let (mut low, mut high) = (0.0, nodes.max(1.0));
+        for _ in 0..100 {
+            let middle = (low + high) / 2.0;
+            if tree_size(middle) < nodes {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        Some((low + high) / 2.0)
+    }
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} expanded, {} generated, {} duplicates pruned, frontier of {} at most, {:.3}s",
+            self.nodes_expanded,
+            self.nodes_generated,
+            self.duplicates_pruned,
+            self.max_frontier_size,
+            self.elapsed.as_secs_f64()
+        )?;
+        if let Some(branching_factor) = self.effective_branching_factor() {
+            write!(f, ", effective branching factor {:.2}", branching_factor)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        let limits = SearchLimits::unlimited()
+            .with_max_nodes(10)
+            .with_max_time(Duration::from_secs(1))
+            .with_max_memory(1000);
+        assert_eq!(limits.exceeded(9, Duration::ZERO, 999), None);
+        assert_eq!(limits.exceeded(10, Duration::ZERO, 0), Some(Limit::Nodes));
+        assert_eq!(limits.exceeded(0, Duration::from_secs(2), 0), Some(Limit::Time));
+        assert_eq!(limits.exceeded(0, Duration::ZERO, 1000), Some(Limit::Memory));
+        assert_eq!(SearchLimits::default().exceeded(usize::MAX, Duration::MAX, usize::MAX), None);
+    }
+
+    #[test]
+    fn cancellation_is_shared_by_clones() {
+        let token = CancellationToken::new();
+        let search_token = token.clone();
+        assert!(!search_token.is_cancelled());
+        std::thread::spawn(move || token.cancel()).join().unwrap();
+        assert!(search_token.is_cancelled());
+    }
+
+    #[test]
+    fn effective_branching_factor() {
+        let mut stats = SearchStats {
+            nodes_generated: 2 + 4 + 8,
+            ..Default::default()
+        };
+        assert_eq!(stats.effective_branching_factor(), None);
+        stats.solution_depth = Some(3);
+        assert!((stats.effective_branching_factor().unwrap() - 2.0).abs() < 1e-9);
+    }
+}
@@ -1,61 +1,62 @@
 use super::action::{Actionable, Actions};
-use super::heuristic::Heuristic;
-use crate::core::{CubeElement, CubeLoader, CubeModel};
-use core::mem::size_of;
-use enum_iterator::all;
-use std::{cmp::Reverse, collections::BinaryHeap};
+use super::heuristic::{RelativeHeuristic, SharedHeuristic};
+use super::search::{CancellationToken, Limit, SearchLimits, SearchResult, SearchStats};
+use crate::core::{CubeLoader, CubeModel, LoadError, MaskedModel, PackedCube, ValidationError};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    sync::Arc,
+    time::Instant,
+};
 
-// const STATE_SPACE_MEMORY_BOUND: f64 = 1e4;
-const STATE_SPACE_BOUND: f64 = 1e6;
+const FRONTIER_BOUND: usize = 1_000_000;
 
+/// A state of the search. The cube is packed, so that the many states generated are cheap to
+/// copy and hash, and only converted to a model at the boundary of the API.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CubeState {
-    pub model: CubeModel,
+    cube: PackedCube,
     path_cost: Option<usize>,
     heuristic_cost: Option<usize>,
     actions_taken: Vec<Actions>,
-    visited: bool,
 }
 
 impl CubeState {
-    pub fn from_model(model: CubeModel) -> Self {
-        Self {
-            model,
-            path_cost: None,
-            heuristic_cost: None,
-            actions_taken: Vec::new(),
-            visited: false,
-        }
+    /// Fails if an element of the model is not in a slot of its kind.
+    pub fn from_model(model: CubeModel) -> Result<Self, ValidationError> {
+        Ok(Self::from_packed(PackedCube::try_from(&model)?))
     }
 
-    pub fn elements(&self) -> &[[[CubeElement; 3]; 3]; 3] {
-        self.model.cube_elements()
-    }
-
-    pub fn from_loader(loader: impl CubeLoader) -> Self {
+    fn from_packed(cube: PackedCube) -> Self {
         Self {
-            model: CubeModel::from_loader(loader),
+            cube,
             path_cost: None,
             heuristic_cost: None,
             actions_taken: Vec::new(),
-            visited: false,
         }
     }
 
-    fn actions(&self) -> Vec<Actions> {
-        all::<Actions>().collect()
+    pub fn from_loader(loader: impl CubeLoader) -> Result<Self, LoadError> {
+        let model = CubeModel::from_loader(loader)?;
+        // the loaders build every element from the tiles of a slot of its kind
+        Ok(Self::from_model(model).expect("Loaded models have their elements in slots of their kind"))
     }
 
-    fn set_visited(&mut self) {
-        self.visited = true;
+    pub fn cube(&self) -> &PackedCube {
+        &self.cube
     }
 
-    fn is_visited(&self) -> bool {
-        self.visited
+    pub fn model(&self) -> CubeModel {
+        CubeModel::from(self.cube)
     }
 
-    fn reset_visited(&mut self) {
-        self.visited = false;
+    /// The face turns that keep the actions taken canonical, see `Actions::follows_canonically`.
+    fn actions(&self) -> Vec<Actions> {
+        let last = self.actions_taken.last().copied();
+        Actions::FACE_TURNS
+            .into_iter()
+            .filter(|action| action.follows_canonically(last))
+            .collect()
     }
 
     fn set_path_cost(&mut self, cost: usize) {
@@ -65,93 +66,146 @@ impl CubeState {
     fn set_heuristic_cost(&mut self, cost: usize) {
         self.heuristic_cost = Some(cost);
     }
-
-    fn update_heristic_cost(&mut self) {
-        self.set_heuristic_cost(self.heuristic());
-    }
 }
 
 impl PartialOrd for CubeState {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        let self_cost = self.path_cost? + self.heuristic_cost?;
-        // let self_cost = self.heuristic_cost?;
-        let other_cost = other.path_cost? + other.heuristic_cost?;
-        // let other_cost = other.heuristic_cost?;
-        Some(self_cost.cmp(&other_cost))
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for CubeState {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+        let self_cost = self.path_cost.unwrap() + self.heuristic_cost.unwrap();
+        let other_cost = other.path_cost.unwrap() + other.heuristic_cost.unwrap();
+        self_cost.cmp(&other_cost)
     }
 }
 
 impl Actionable for CubeState {
     fn apply_action(&mut self, action: Actions) {
-        self.model.apply_action(action);
+        self.cube.apply_action(action);
         self.actions_taken.push(action);
     }
 }
 
+/// What a search looks for, a state or any state matching a partial goal.
+enum Goal {
+    State(CubeState),
+    Masked(MaskedModel),
+}
+
 pub struct StateSpace {
-    goal_state: CubeState,
+    goal: Goal,
+    heuristic: SharedHeuristic,
     frontier: BinaryHeap<Reverse<CubeState>>,
+    /// Transposition table with the cheapest path cost found so far for every state reached,
+    /// a state is only pushed again to the frontier through a cheaper path.
+    best_path_costs: HashMap<PackedCube, usize>,
+    /// Size the frontier is cut down to once it grows past twice as much.
+    frontier_bound: usize,
+    limits: SearchLimits,
+    cancellation: CancellationToken,
+    stats: SearchStats,
 }
 
 impl StateSpace {
-    pub fn new(initial_state: CubeState, goal_state: CubeState) -> Self {
+    /// A search from `initial_state` to `goal_state` guided by `heuristic`, e.g. one picked from
+    /// a `HeuristicRegistry`. Solutions are optimal when the heuristic is admissible and the
+    /// frontier never grows past its bound: pruning it may drop the states of the optimal path.
+    /// The heuristic estimates the distance to the solved cube, it is made relative to the goal
+    /// when the goal is another state, see `RelativeHeuristic`.
+    /// The search runs without limits, see `with_limits` and `with_cancellation`.
+    pub fn new(initial_state: CubeState, goal_state: CubeState, heuristic: SharedHeuristic) -> Self {
+        let heuristic = match goal_state.cube.is_solved() {
+            true => heuristic,
+            // an invalid goal is reported by solve
+            false => match RelativeHeuristic::new(heuristic.clone(), &goal_state.model()) {
+                Ok(relative) => Arc::new(relative),
+                Err(_) => heuristic,
+            },
+        };
+        Self::with_goal(initial_state, Goal::State(goal_state), heuristic)
+    }
+
+    /// A search from `initial_state` to any state matching `goal`. The heuristic is always the
+    /// goal itself, counting the mismatched corners and edges a quarter per face turn, see its
+    /// `ModelHeuristic` implementation, which is admissible but weak.
+    pub fn new_masked(initial_state: CubeState, goal: MaskedModel) -> Self {
+        let heuristic = Arc::new(goal.clone());
+        Self::with_goal(initial_state, Goal::Masked(goal), heuristic)
+    }
+
+    fn with_goal(initial_state: CubeState, goal: Goal, heuristic: SharedHeuristic) -> Self {
         let mut heap = BinaryHeap::new();
         let mut root = initial_state.clone();
         root.set_path_cost(0);
-        root.set_heuristic_cost(100);
+        root.set_heuristic_cost(heuristic.estimate_packed(&root.cube));
+        let best_path_costs = HashMap::from([(root.cube, 0)]);
         heap.push(Reverse(root));
 
         Self {
-            goal_state,
+            goal,
+            heuristic,
             frontier: heap,
+            best_path_costs,
+            frontier_bound: FRONTIER_BOUND,
+            limits: SearchLimits::unlimited(),
+            cancellation: CancellationToken::new(),
+            stats: SearchStats::default(),
         }
     }
 
+    /// Stops the search once any of the limits is hit.
+    pub fn with_limits(mut self, limits: SearchLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Stops the search once the token is cancelled, e.g. from another thread.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
     fn add_to_frontier(&mut self, state: CubeState) {
         self.frontier.push(Reverse(state));
     }
 
     fn prune_frontier(&mut self) {
-        // let mut new_frontier = BinaryHeap::new();
-        // let mut visited = Vec::new();
-        // let state_bound = STATE_SPACE_MEMORY_BOUND / size_of::<CubeState>() as f64;
-
-        if self.frontier.len() > (STATE_SPACE_BOUND * 2.0) as usize {
-            let state_bound = STATE_SPACE_BOUND;
-            let mut state_remaining = state_bound as usize;
-            let old_front = self.frontier.clone();
-            self.frontier = BinaryHeap::with_capacity(state_remaining);
-            old_front.into_sorted_vec()
-                .iter()
-                .rev()
-                .for_each(|Reverse(state)| {
-                    if state_remaining > 0 {
-                        self.frontier.push(Reverse(state.clone()));
-                        state_remaining -= 1;
-                    }
-                });
+        if self.frontier.len() > self.frontier_bound * 2 {
+            let mut kept = std::mem::take(&mut self.frontier).into_sorted_vec();
+            // the most promising states come last
+            let dropped = kept.drain(..kept.len() - self.frontier_bound);
+            // a dropped state is forgotten, so that it can be pushed again through another path
+            for Reverse(state) in dropped {
+                if self.best_path_costs.get(&state.cube) == state.path_cost.as_ref() {
+                    self.best_path_costs.remove(&state.cube);
+                }
+            }
+            self.frontier = BinaryHeap::from(kept);
         }
-        // while let Some(Reverse(state)) = self.frontier.pop() {
-        //     if !visited.contains(&state) {
-        //         visited.push(state.clone());
-        //         new_frontier.push(Reverse(state));
-        //     }
-        //     if state_remaining == 0 {
-        //         break;
-        //     }
-        //     state_remaining -= 1;
-        // }
-        // self.frontier = new_frontier;
     }
 
     fn is_goal(&self, state: &CubeState) -> bool {
-        state == &self.goal_state
+        match &self.goal {
+            Goal::State(goal_state) => state.cube == goal_state.cube,
+            Goal::Masked(goal) => goal.matches_packed(&state.cube),
+        }
+    }
+
+    /// False when no face turns lead from the cube to the goal: face turns never move the
+    /// centers, and keep the cube solvable.
+    fn is_reachable(&self, cube: &PackedCube) -> bool {
+        if !CubeModel::from(*cube).is_solvable() {
+            return false;
+        }
+        match &self.goal {
+            Goal::State(goal_state) => {
+                goal_state.model().is_solvable() && (0..6).all(|slot| cube.center(slot) == goal_state.cube.center(slot))
+            }
+            Goal::Masked(goal) => goal.centers_match(cube),
+        }
     }
 
     fn get_successors(&self, state: &CubeState) -> Vec<CubeState> {
@@ -167,32 +221,300 @@ impl StateSpace {
     }
 
     fn expand_frontier(&mut self) {
-        // let mut new_frontier = BinaryHeap::new();
         let Reverse(promising_s) = self.frontier.pop().unwrap();
-        let successors = self.get_successors(&promising_s);
         let promising_s_path_cost = promising_s.path_cost.unwrap();
-        for successor in successors {
-            let mut new_state = successor.clone();
-            new_state.set_path_cost(promising_s_path_cost + 1);
-            new_state.update_heristic_cost();
-            self.add_to_frontier(new_state);
+        // a cheaper path to this state was found after it was pushed, or it was dropped
+        if self.best_path_costs.get(&promising_s.cube).is_none_or(|best| *best < promising_s_path_cost) {
+            self.stats.duplicates_pruned += 1;
+            return;
+        }
+        self.stats.nodes_expanded += 1;
+        for mut successor in self.get_successors(&promising_s) {
+            self.stats.nodes_generated += 1;
+            let path_cost = promising_s_path_cost + 1;
+            match self.best_path_costs.get(&successor.cube) {
+                Some(best) if *best <= path_cost => {
+                    self.stats.duplicates_pruned += 1;
+                    continue;
+                }
+                _ => self.best_path_costs.insert(successor.cube, path_cost),
+            };
+            successor.set_path_cost(path_cost);
+            successor.set_heuristic_cost(self.heuristic.estimate_packed(&successor.cube));
+            self.add_to_frontier(successor);
         }
     }
 
-    pub fn solve(&mut self) -> Option<Vec<Actions>> {
-        while !self.frontier.is_empty() {
+    /// Rough number of bytes held by the search: the states of the frontier, with their paths,
+    /// and the transposition table.
+    fn memory_usage(&self, depth: usize) -> usize {
+        let state = size_of::<CubeState>() + depth * size_of::<Actions>();
+        let entry = size_of::<PackedCube>() + size_of::<usize>();
+        self.frontier.len() * state + self.best_path_costs.len() * entry
+    }
+
+    /// Searches until the goal is reached, a limit is hit or the search is cancelled.
+    pub fn solve(&mut self) -> (SearchResult, SearchStats) {
+        self.solve_with(|_| {})
+    }
+
+    /// Same as `solve`, calling `observer` with the statistics of the search after every
+    /// expansion.
+    pub fn solve_with(&mut self, mut observer: impl FnMut(&SearchStats)) -> (SearchResult, SearchStats) {
+        let start = Instant::now();
+        let result = self.search(start, &mut observer);
+        self.stats.elapsed = start.elapsed();
+        if let SearchResult::Solved(actions) = &result {
+            self.stats.solution_depth = Some(actions.len());
+        }
+        (result, self.stats.clone())
+    }
+
+    fn search(&mut self, start: Instant, observer: &mut impl FnMut(&SearchStats)) -> SearchResult {
+        // a state that cannot be reached would be searched forever
+        let Some(Reverse(root)) = self.frontier.peek() else {
+            return SearchResult::Unsolvable;
+        };
+        if !self.is_reachable(&root.cube) {
+            return SearchResult::Unsolvable;
+        }
+        let mut closest = root.clone();
+        while let Some(Reverse(state)) = self.frontier.peek() {
+            if self.is_goal(state) {
+                return SearchResult::Solved(state.actions_taken.clone());
+            }
+            if state.heuristic_cost < closest.heuristic_cost {
+                closest = state.clone();
+            }
+            let contour = state.path_cost.unwrap() + state.heuristic_cost.unwrap();
+            if self.stats.contours.last().is_none_or(|last| contour > *last) {
+                self.stats.contours.push(contour);
+            }
+            let depth = state.path_cost.unwrap() + 1;
+            let limit = match self.cancellation.is_cancelled() {
+                true => Some(Limit::Cancelled),
+                false => self.limits.exceeded(self.stats.nodes_expanded, start.elapsed(), self.memory_usage(depth)),
+            };
+            if let Some(limit) = limit {
+                return SearchResult::LimitReached {
+                    limit,
+                    best_path: closest.actions_taken,
+                };
+            }
             self.prune_frontier();
             self.expand_frontier();
-            println!("Frontier size: {}", self.frontier.len());
-            let Reverse(min_s) = self.frontier.peek().unwrap();
-            println!("Frontier min: {}-{}", min_s.path_cost.unwrap(), min_s.heuristic_cost.unwrap());
-            println!("Contour: {}", min_s.path_cost.unwrap() + min_s.heuristic_cost.unwrap());
-            if let Some(Reverse(state)) = self.frontier.peek() {
-                if state.heuristic_cost.unwrap() == 0 {
-                    return Some(state.actions_taken.clone());
-                }
+            self.stats.frontier_size = self.frontier.len();
+            self.stats.max_frontier_size = self.stats.max_frontier_size.max(self.frontier.len());
+            self.stats.elapsed = start.elapsed();
+            observer(&self.stats);
+        }
+        SearchResult::Unsolvable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::{Algorithm, HeuristicRegistry};
+    use crate::core::Face;
+    use std::time::Duration;
+
+    fn state_space_with(scramble: &str, heuristic: &str) -> StateSpace {
+        let mut model = CubeModel::solved();
+        scramble.parse::<Algorithm>().unwrap().apply_to(&mut model);
+        StateSpace::new(
+            CubeState::from_model(model).unwrap(),
+            CubeState::from_model(CubeModel::solved()).unwrap(),
+            HeuristicRegistry::default().get(heuristic).unwrap(),
+        )
+    }
+
+    fn state_space(scramble: &str) -> StateSpace {
+        state_space_with(scramble, "misplaced-cubies")
+    }
+
+    #[test]
+    fn solves_with_an_admissible_heuristic() {
+        for (scramble, length) in [("R", 1), ("R U2", 2), ("F' L D", 3)] {
+            let solution = state_space(scramble).solve().0.into_solution().unwrap();
+            assert_eq!(solution.len(), length, "{}", scramble);
+        }
+    }
+
+    #[test]
+    fn duplicate_states_are_not_pushed_again() {
+        let mut space = state_space("R U F'");
+        // a state already reached as cheaply through another path
+        let Reverse(root) = space.frontier.peek().unwrap();
+        let mut reached = root.cube;
+        reached.apply_action(Actions::U2);
+        space.best_path_costs.insert(reached, 1);
+        space.expand_frontier();
+        assert_eq!(space.frontier.len(), 17);
+        assert_eq!(space.best_path_costs.len(), 1 + 18);
+    }
+
+    #[test]
+    fn successors_are_canonical() {
+        let mut space = state_space("R U F'");
+        space.expand_frontier();
+        assert_eq!(space.frontier.len(), 18);
+        space.expand_frontier();
+        // the face turned last is not turned again, nor its opposite face when it comes first
+        assert!([17 + 15, 17 + 12].contains(&space.frontier.len()));
+        for Reverse(state) in space.frontier.iter() {
+            for pair in state.actions_taken.windows(2) {
+                assert!(pair[1].follows_canonically(Some(pair[0])));
             }
         }
-        None
+    }
+
+    #[test]
+    fn pruned_states_can_be_reached_again() {
+        let mut model = CubeModel::solved();
+        "R U".parse::<Algorithm>().unwrap().apply_to(&mut model);
+        let mut space = state_space("R U");
+        space.expand_frontier();
+        let children: Vec<_> = space.frontier.iter().map(|Reverse(state)| state.cube).collect();
+        space.frontier_bound = 8;
+        space.prune_frontier();
+        assert_eq!(space.frontier.len(), 8);
+        // only the root and the states kept are known
+        assert_eq!(space.best_path_costs.len(), 1 + 8);
+        let dropped = children.iter().filter(|child| !space.frontier.iter().any(|Reverse(state)| state.cube == **child));
+        assert!(dropped.into_iter().all(|child| !space.best_path_costs.contains_key(child)));
+        let solution = space.solve().0.into_solution().unwrap();
+        Algorithm::from(solution).apply_to(&mut model);
+        assert!(model.is_solved());
+    }
+
+    #[test]
+    fn solved_cube() {
+        assert_eq!(state_space("").solve().0, SearchResult::Solved(vec![]));
+    }
+
+    #[test]
+    fn unsolvable_cube() {
+        let model = CubeModel::solved();
+        let mut orientations = *model.orientations();
+        orientations[2][2][0] = 1;
+        let mut space = StateSpace::new(
+            CubeState::from_model(CubeModel::new(*model.cube_elements(), orientations)).unwrap(),
+            CubeState::from_model(CubeModel::solved()).unwrap(),
+            HeuristicRegistry::default().get("misplaced-cubies").unwrap(),
+        );
+        assert_eq!(space.solve().0, SearchResult::Unsolvable);
+
+        // an edge in a corner slot cannot even be packed
+        let mut elements = *model.cube_elements();
+        elements[2][2][0] = elements[1][2][0];
+        assert!(matches!(
+            CubeState::from_model(CubeModel::new(elements, *model.orientations())),
+            Err(ValidationError::MisplacedElement { position: (2, 2, 0), .. })
+        ));
+    }
+
+    const HARD_SCRAMBLE: &str = "R U F' L2 D B' R2 U' F L' D2 B";
+
+    #[test]
+    fn node_limit() {
+        // a finer heuristic than the misplaced cubies, so that the closest state is not the root
+        let mut space = state_space_with("R U F' L", "misplaced-tiles").with_limits(SearchLimits::unlimited().with_max_nodes(3));
+        let Reverse(root) = space.frontier.peek().unwrap();
+        let mut model = root.model();
+        let root_cost = root.heuristic_cost.unwrap();
+        match space.solve().0 {
+            SearchResult::LimitReached { limit, best_path } => {
+                assert_eq!(limit, Limit::Nodes);
+                Algorithm::from(best_path).apply_to(&mut model);
+                assert!(space.heuristic.estimate(&model) < root_cost);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn time_and_memory_limits() {
+        let limits = SearchLimits::unlimited().with_max_time(Duration::from_millis(20));
+        let (result, _) = state_space(HARD_SCRAMBLE).with_limits(limits).solve();
+        assert!(matches!(result, SearchResult::LimitReached { limit: Limit::Time, .. }));
+
+        let limits = SearchLimits::unlimited().with_max_memory(100_000);
+        let (result, _) = state_space(HARD_SCRAMBLE).with_limits(limits).solve();
+        assert!(matches!(result, SearchResult::LimitReached { limit: Limit::Memory, .. }));
+    }
+
+    #[test]
+    fn cancellation_from_another_thread() {
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            canceller.cancel();
+        });
+        // the time limit only keeps the test from hanging
+        let limits = SearchLimits::unlimited().with_max_time(Duration::from_secs(60));
+        let (result, _) = state_space(HARD_SCRAMBLE).with_limits(limits).with_cancellation(token).solve();
+        handle.join().unwrap();
+        assert!(matches!(result, SearchResult::LimitReached { limit: Limit::Cancelled, .. }));
+    }
+
+    #[test]
+    fn statistics_are_reported() {
+        let mut observed = Vec::new();
+        let (result, stats) = state_space("R U2 F'").solve_with(|stats| observed.push(stats.clone()));
+        assert_eq!(result.solution().unwrap().len(), 3);
+        assert_eq!(stats.solution_depth, Some(3));
+        // stale states popped from the frontier are reported too, without being expanded
+        assert!(observed.len() >= stats.nodes_expanded);
+        assert_eq!(observed.last().unwrap().nodes_expanded, stats.nodes_expanded);
+        assert!(stats.nodes_generated >= stats.nodes_expanded);
+        assert!(stats.max_frontier_size >= stats.frontier_size);
+        assert!(stats.contours.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(*stats.contours.last().unwrap(), 3);
+        assert!(stats.effective_branching_factor().unwrap() > 1.0);
+    }
+
+    #[test]
+    fn solves_between_two_states() {
+        let scrambled = |scramble: &str| {
+            let mut model = CubeModel::solved();
+            scramble.parse::<Algorithm>().unwrap().apply_to(&mut model);
+            model
+        };
+        let start = scrambled("R U F'");
+        let goal = scrambled("R U2 F' B");
+        let mut space = StateSpace::new(
+            CubeState::from_model(start.clone()).unwrap(),
+            CubeState::from_model(goal.clone()).unwrap(),
+            HeuristicRegistry::default().get("misplaced-cubies").unwrap(),
+        );
+        // the relative heuristic knows the goal is close, the absolute one would not
+        assert!(space.frontier.peek().unwrap().0.heuristic_cost.unwrap() <= 3);
+        let solution = space.solve().0.into_solution().unwrap();
+        assert!(solution.len() <= 5);
+        let mut model = start;
+        Algorithm::from(solution).apply_to(&mut model);
+        assert_eq!(model, goal);
+
+        // no face turn rotates the cube
+        let mut space = StateSpace::new(
+            CubeState::from_model(CubeModel::solved()).unwrap(),
+            CubeState::from_model(scrambled("R x")).unwrap(),
+            HeuristicRegistry::default().get("misplaced-cubies").unwrap(),
+        );
+        assert_eq!(space.solve().0, SearchResult::Unsolvable);
+    }
+
+    #[test]
+    fn solves_partial_goals() {
+        let mut model = CubeModel::solved();
+        "R U F' L2".parse::<Algorithm>().unwrap().apply_to(&mut model);
+        let goal = MaskedModel::cross(Face::Down);
+        let mut space = StateSpace::new_masked(CubeState::from_model(model.clone()).unwrap(), goal.clone());
+        let solution = space.solve().0.into_solution().unwrap();
+        Algorithm::from(solution).apply_to(&mut model);
+        assert!(goal.matches(&model));
+        assert!(!model.is_solved());
     }
 }
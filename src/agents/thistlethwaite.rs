@@ -0,0 +1,318 @@
+use super::action::Actions;
+use super::algorithm::{Algorithm, StagedSolution};
+use super::two_phase::{face_turn_cube, pruning_table};
+use crate::core::{CubeModel, PackedCube};
+use std::collections::HashMap;
+
+/// A property of the cube, given by an identifier, with its values numbered in the order a
+/// breadth first search from the solved cube reaches them.
+struct Coordinate {
+    id: fn(&PackedCube) -> u128,
+    indexes: HashMap<u128, u16>,
+    /// For every value and every move, the value after the move.
+    moves: Vec<u16>,
+}
+
+impl Coordinate {
+    fn new(id: fn(&PackedCube) -> u128, moves: &[PackedCube]) -> Self {
+        let mut indexes = HashMap::from([(id(&PackedCube::SOLVED), 0)]);
+        let mut cubes = vec![PackedCube::SOLVED];
+        let mut table = Vec::new();
+        let mut i = 0;
+        while i < cubes.len() {
+            for moves_cube in moves {
+                let next = cubes[i].multiply(moves_cube);
+                let count = indexes.len() as u16;
+                let index = *indexes.entry(id(&next)).or_insert_with(|| {
+                    cubes.push(next);
+                    count
+                });
+                table.push(index);
+            }
+            i += 1;
+        }
+        Self {
+            id,
+            indexes,
+            moves: table,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.indexes.len()
+    }
+
+    /// None for a value the moves never reach from the solved cube.
+    fn index(&self, cube: &PackedCube) -> Option<usize> {
+        self.indexes.get(&(self.id)(cube)).map(|index| *index as usize)
+    }
+}
+
+/// One step of the reduction: the moves of the current group, and two coordinates which
+/// together are the same for all the cubes of a coset of the next group. The step is done
+/// when both have the value of the solved cube.
+struct Phase {
+    name: &'static str,
+    actions: Vec<Actions>,
+    moves: Vec<PackedCube>,
+    first: Coordinate,
+    second: Coordinate,
+    /// Moves left to finish the step for every pair of values.
+    distances: Vec<u8>,
+}
+
+impl Phase {
+    fn new(
+        name: &'static str,
+        actions: &[Actions],
+        first: fn(&PackedCube) -> u128,
+        second: fn(&PackedCube) -> u128,
+    ) -> Self {
+        let moves: Vec<_> = actions.iter().map(|action| *action.packed()).collect();
+        let first = Coordinate::new(first, &moves);
+        let second = Coordinate::new(second, &moves);
+        let distances = pruning_table(
+            (&first.moves, first.len(), 0),
+            (&second.moves, second.len(), 0),
+            moves.len(),
+        );
+        Self {
+            name,
+            actions: actions.to_vec(),
+            moves,
+            first,
+            second,
+            distances,
+        }
+    }
+
+    /// Follows the table down to the next group, taking the first move that gets closer. None
+    /// if the cube is not in the group of the phase.
+    fn solve(&self, cube: &mut PackedCube) -> Option<Algorithm> {
+        let (mut first, mut second) = (self.first.index(cube)?, self.second.index(cube)?);
+        let mut actions = Vec::new();
+        let mut distance = self.distances[first * self.second.len() + second];
+        // the pair of values is never reached by the moves of the phase
+        if distance == u8::MAX {
+            return None;
+        }
+        while distance > 0 {
+            let count = self.moves.len();
+            let i = (0..count)
+                .find(|i| {
+                    let next_first = self.first.moves[first * count + i] as usize;
+                    let next_second = self.second.moves[second * count + i] as usize;
+                    self.distances[next_first * self.second.len() + next_second] < distance
+                })?;
+            first = self.first.moves[first * count + i] as usize;
+            second = self.second.moves[second * count + i] as usize;
+            actions.push(self.actions[i]);
+            *cube = cube.multiply(&self.moves[i]);
+            distance -= 1;
+        }
+        Some(Algorithm::new(actions))
+    }
+}
+
+fn nothing(_: &PackedCube) -> u128 {
+    0
+}
+
+/// G0 to G1: all the edges are oriented, so F and B are only turned by half turns.
+fn edge_orientations(cube: &PackedCube) -> u128 {
+    (0..12).fold(0, |id, slot| id << 1 | cube.edge(slot).1 as u128)
+}
+
+/// G1 to G2: all the corners are oriented...
+fn corner_orientations(cube: &PackedCube) -> u128 {
+    (0..8).fold(0, |id, slot| id << 2 | cube.corner(slot).1 as u128)
+}
+
+/// ...and the middle layer edges are in the middle layer, so R and L are only turned by half turns.
+fn middle_layer_edges(cube: &PackedCube) -> u128 {
+    cube.edge_pieces().iter().fold(0, |id, piece| id << 1 | (*piece >= 8) as u128)
+}
+
+/// G2 to G3: every corner is in one of the two slots of its diagonal of the Up or Down face,
+/// and the corner permutation is even...
+fn corner_diagonals(cube: &PackedCube) -> u128 {
+    // corners on the same diagonal only differ by the bit 1 of their index
+    let pieces = cube.corner_pieces();
+    let diagonals = pieces.iter().fold(0, |id, piece| id << 3 | (*piece & 5) as u128);
+    let mut parity = 0;
+    for (i, piece) in pieces.iter().enumerate() {
+        parity ^= pieces[i + 1..].iter().filter(|p| *p < piece).count() & 1;
+    }
+    diagonals << 1 | parity as u128
+}
+
+/// ...and every edge is in the slice it belongs to, so U and D are only turned by half turns.
+/// This is the identifier used by Stefan Pochmann, finer than the cosets of G3.
+fn edge_slices(cube: &PackedCube) -> u128 {
+    // UR, UL, DR, DL belong to the S slice, UF, UB, DF, DB to the M slice
+    cube.edge_pieces().iter().fold(0, |id, piece| {
+        let slice = if *piece >= 8 { 2 } else { *piece & 1 };
+        id << 2 | slice as u128
+    })
+}
+
+/// G3 to G4: the cube is solved.
+fn corner_pieces(cube: &PackedCube) -> u128 {
+    cube.corner_pieces().iter().fold(0, |id, piece| id << 3 | *piece as u128)
+}
+
+fn edge_pieces(cube: &PackedCube) -> u128 {
+    cube.edge_pieces().iter().fold(0, |id, piece| id << 4 | *piece as u128)
+}
+
+/// Thistlethwaite's algorithm, which solves the cube through the nested groups
+/// G0 = <U, D, F, B, R, L>, G1 = <U, D, F2, B2, R, L>, G2 = <U, D, F2, B2, R2, L2> and
+/// G3 = <U2, D2, F2, B2, R2, L2>, with a lookup table for each step. Solutions are at most
+/// 45 moves long, usually about 32, and always the same for a given cube.
+pub struct Thistlethwaite {
+    phases: [Phase; 4],
+}
+
+impl Default for Thistlethwaite {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Thistlethwaite {
+    pub fn new() -> Self {
+        use Actions::*;
+        Self {
+            phases: [
+                Phase::new("G0 -> G1", &Actions::FACE_TURNS, edge_orientations, nothing),
+                Phase::new(
+                    "G1 -> G2",
+                    &[U, UC, U2, D, DC, D2, F2, B2, R, RC, R2, L, LC, L2],
+                    corner_orientations,
+                    middle_layer_edges,
+                ),
+                Phase::new(
+                    "G2 -> G3",
+                    &[U, UC, U2, D, DC, D2, F2, B2, R2, L2],
+                    corner_diagonals,
+                    edge_slices,
+                ),
+                Phase::new("G3 -> G4", &[U2, D2, F2, B2, R2, L2], corner_pieces, edge_pieces),
+            ],
+        }
+    }
+
+    /// Returns the moves of each step, None if the cube cannot be solved by face turns.
+    pub fn solve(&self, model: &CubeModel) -> Option<StagedSolution> {
+        let mut cube = face_turn_cube(model)?;
+        let mut solution = StagedSolution::new();
+        for phase in self.phases.iter() {
+            solution.push_stage(phase.name, phase.solve(&mut cube)?);
+        }
+        Some(solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::Actionable;
+    use crate::core::Face;
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+    use std::sync::OnceLock;
+
+    fn solver() -> &'static Thistlethwaite {
+        static SOLVER: OnceLock<Thistlethwaite> = OnceLock::new();
+        SOLVER.get_or_init(Thistlethwaite::new)
+    }
+
+    fn scrambled(scramble: &str) -> CubeModel {
+        let mut model = CubeModel::solved();
+        scramble.parse::<Algorithm>().unwrap().apply_to(&mut model);
+        model
+    }
+
+    #[test]
+    fn table_sizes() {
+        let sizes = solver()
+            .phases
+            .iter()
+            .map(|phase| phase.distances.iter().filter(|d| **d != u8::MAX).count())
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [2048, 1082565, 352800, 663552]);
+    }
+
+    #[test]
+    fn solved_cube() {
+        let solution = solver().solve(&CubeModel::solved()).unwrap();
+        assert!(solution.is_empty());
+        assert_eq!(solution.stages().len(), 4);
+    }
+
+    #[test]
+    fn solves_scrambles() {
+        for scramble in [
+            "R U F' L2 D B'",
+            "D2 F' U2 L' B R2 D' F2 U R' B2 L D2 F U' R2 B' L2 U F' D R B2 L' U2",
+            "F R U R' U' F' U2 B L2 D' R F2 U L' B2 D R' F U2 L D2 B' R2 U' F2",
+        ] {
+            let mut model = scrambled(scramble);
+            let solution = solver().solve(&model).unwrap();
+            assert!(solution.len() <= 45, "{}", solution);
+            solution.algorithm().apply_to(&mut model);
+            assert!(model.is_solved(), "{}", scramble);
+        }
+    }
+
+    #[test]
+    fn solves_random_scrambles() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            let scramble = Algorithm::new((0..30).map(|_| *Actions::FACE_TURNS.choose(&mut rng).unwrap()).collect());
+            let mut model = CubeModel::solved();
+            scramble.apply_to(&mut model);
+            let solution = solver().solve(&model).unwrap();
+            assert!(solution.len() <= 45, "{}", solution);
+            solution.algorithm().apply_to(&mut model);
+            assert!(model.is_solved(), "{}", scramble);
+        }
+    }
+
+    #[test]
+    fn cubes_outside_the_group_of_a_phase() {
+        // a quarter turn of R leaves G2 and G3, where R is only turned by half turns
+        let mut cube = PackedCube::SOLVED;
+        cube.apply_action(Actions::R);
+        assert!(solver().phases[3].solve(&mut cube.clone()).is_none());
+        assert!(solver().phases[2].solve(&mut cube.clone()).is_none());
+        assert!(solver().phases[1].solve(&mut cube).is_some());
+    }
+
+    #[test]
+    fn phases_stay_in_their_group() {
+        let model = scrambled("R U F' L2 D B' R2 U' F2 D'");
+        let solution = solver().solve(&model).unwrap();
+        let stages = solution.stages();
+        assert_eq!(stages[0].0, "G0 -> G1");
+        let quarter_turns = |stage: &Algorithm, faces: &[Face]| {
+            stage
+                .actions()
+                .iter()
+                .any(|action| faces.contains(&action.face().unwrap()) && action.quarter_turns() != 2)
+        };
+        assert!(!quarter_turns(&stages[1].1, &[Face::Front, Face::Back]));
+        assert!(!quarter_turns(&stages[2].1, &[Face::Front, Face::Back, Face::Right, Face::Left]));
+        assert!(stages[3].1.actions().iter().all(|action| action.quarter_turns() == 2));
+
+        let mut model = model;
+        stages[0].1.apply_to(&mut model);
+        assert_eq!(PackedCube::try_from(&model).unwrap().edge_orientation(), 0);
+    }
+
+    #[test]
+    fn unsolvable_cube() {
+        let mut model = CubeModel::solved();
+        model.apply_action(Actions::X);
+        assert!(solver().solve(&model).is_none());
+    }
+}
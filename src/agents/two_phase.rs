@@ -0,0 +1,468 @@
+use super::action::Actions;
+use crate::core::{CubeModel, PackedCube};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+const TWIST_COUNT: usize = 2187;
+const FLIP_COUNT: usize = 2048;
+/// Ways to choose the 4 slots of the middle layer edges among the 12 edge slots.
+const SLICE_COUNT: usize = 495;
+const CORNER_PERMUTATION_COUNT: usize = 40320;
+const EDGE_PERMUTATION_COUNT: usize = 40320;
+const SLICE_PERMUTATION_COUNT: usize = 24;
+
+/// Phase one never needs more moves, and phase two at most 18, see Kociemba.
+const MAX_PHASE_ONE: usize = 12;
+const MAX_PHASE_TWO: usize = 18;
+
+/// The moves generating the subgroup <U, D, R2, L2, F2, B2> solved by phase two.
+const PHASE_TWO_MOVES: [Actions; 10] = [
+    Actions::U,
+    Actions::UC,
+    Actions::U2,
+    Actions::D,
+    Actions::DC,
+    Actions::D2,
+    Actions::F2,
+    Actions::B2,
+    Actions::R2,
+    Actions::L2,
+];
+
+fn twist(cube: &PackedCube) -> usize {
+    cube.corner_orientation() as usize
+}
+
+pub(super) fn flip(cube: &PackedCube) -> usize {
+    cube.edge_orientation() as usize
+}
+
+/// Slots holding the middle layer edges, whatever their order.
+fn slice(cube: &PackedCube) -> usize {
+    cube.edge_pieces()
+        .iter()
+        .enumerate()
+        .filter(|(_, piece)| **piece >= 8)
+        .enumerate()
+        .map(|(i, (slot, _))| binomial(slot, i + 1))
+        .sum()
+}
+
+fn corner_permutation(cube: &PackedCube) -> usize {
+    cube.corner_permutation() as usize
+}
+
+/// Permutation of the Up and Down layer edges, only meaningful in phase two.
+fn edge_permutation(cube: &PackedCube) -> usize {
+    permutation_rank(&cube.edge_pieces()[..8])
+}
+
+/// Permutation of the middle layer edges, only meaningful in phase two.
+fn slice_permutation(cube: &PackedCube) -> usize {
+    permutation_rank(&cube.edge_pieces()[8..])
+}
+
+/// The packed cube of a model that face turns can solve, None if its centers are moved or it
+/// is not solvable.
+pub(super) fn face_turn_cube(model: &CubeModel) -> Option<PackedCube> {
+    let cube = PackedCube::try_from(model).ok()?;
+    (cube.centers_solved() && model.is_solvable()).then_some(cube)
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+}
+
+/// Rank of the permutation of distinct values in the factorial number system.
+fn permutation_rank(values: &[u8]) -> usize {
+    values.iter().enumerate().fold(0, |rank, (i, value)| {
+        let smaller = values[i + 1..].iter().filter(|v| *v < value).count();
+        rank * (values.len() - i) + smaller
+    })
+}
+
+/// For every value of a coordinate and every move, the value after the move. Each value is
+/// reached from the solved cube by a breadth first search over cubes representing them.
+pub(super) fn move_table(size: usize, coordinate: fn(&PackedCube) -> usize, moves: &[PackedCube]) -> Vec<u16> {
+    let mut table = vec![u16::MAX; size * moves.len()];
+    let mut reached = vec![false; size];
+    reached[coordinate(&PackedCube::SOLVED)] = true;
+    let mut queue = VecDeque::from([PackedCube::SOLVED]);
+    while let Some(cube) = queue.pop_front() {
+        let value = coordinate(&cube);
+        for (i, moves_cube) in moves.iter().enumerate() {
+            let next = cube.multiply(moves_cube);
+            let next_value = coordinate(&next);
+            table[value * moves.len() + i] = next_value as u16;
+            if !reached[next_value] {
+                reached[next_value] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    debug_assert!(table.iter().all(|value| *value != u16::MAX));
+    table
+}
+
+/// Moves needed to bring a pair of coordinates to their solved values, for every pair,
+/// indexed by `first * second_size + second`.
+pub(super) fn pruning_table(
+    (first_moves, first_size, first_solved): (&[u16], usize, usize),
+    (second_moves, second_size, second_solved): (&[u16], usize, usize),
+    move_count: usize,
+) -> Vec<u8> {
+    let mut table = vec![u8::MAX; first_size * second_size];
+    table[first_solved * second_size + second_solved] = 0;
+    let mut depth = 0;
+    let mut reached = 1;
+    while reached > 0 {
+        reached = 0;
+        for index in 0..table.len() {
+            if table[index] != depth {
+                continue;
+            }
+            let (first, second) = (index / second_size, index % second_size);
+            for i in 0..move_count {
+                let next = first_moves[first * move_count + i] as usize * second_size
+                    + second_moves[second * move_count + i] as usize;
+                if table[next] == u8::MAX {
+                    table[next] = depth + 1;
+                    reached += 1;
+                }
+            }
+        }
+        depth += 1;
+    }
+    table
+}
+
+/// Kociemba's two-phase algorithm. Phase one brings the cube into the subgroup
+/// <U, D, R2, L2, F2, B2>, where all the orientations are solved and the middle layer edges are
+/// in the middle layer, and phase two solves it using only the moves of that subgroup.
+/// Longer phase one solutions are then tried in turn, which yields shorter solutions over time.
+/// Building the tables takes a moment, a solver should be reused for many cubes.
+pub struct TwoPhase {
+    twist_moves: Vec<u16>,
+    flip_moves: Vec<u16>,
+    slice_moves: Vec<u16>,
+    corner_permutation_moves: Vec<u16>,
+    edge_permutation_moves: Vec<u16>,
+    slice_permutation_moves: Vec<u16>,
+    twist_pruning: Vec<u8>,
+    flip_pruning: Vec<u8>,
+    corner_pruning: Vec<u8>,
+    edge_pruning: Vec<u8>,
+    target_length: usize,
+}
+
+impl Default for TwoPhase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TwoPhase {
+    pub fn new() -> Self {
+        let face_turns = Actions::FACE_TURNS.map(|action| *action.packed());
+        let phase_two_moves = PHASE_TWO_MOVES.map(|action| *action.packed());
+        let solved_slice = slice(&PackedCube::SOLVED);
+
+        let twist_moves = move_table(TWIST_COUNT, twist, &face_turns);
+        let flip_moves = move_table(FLIP_COUNT, flip, &face_turns);
+        let slice_moves = move_table(SLICE_COUNT, slice, &face_turns);
+        let corner_permutation_moves = move_table(
+            CORNER_PERMUTATION_COUNT,
+            corner_permutation,
+            &phase_two_moves,
+        );
+        let edge_permutation_moves = move_table(
+            EDGE_PERMUTATION_COUNT,
+            edge_permutation,
+            &phase_two_moves,
+        );
+        let slice_permutation_moves = move_table(
+            SLICE_PERMUTATION_COUNT,
+            slice_permutation,
+            &phase_two_moves,
+        );
+
+        let slice = (&slice_moves[..], SLICE_COUNT, solved_slice);
+        let slice_permutation = (&slice_permutation_moves[..], SLICE_PERMUTATION_COUNT, 0);
+        Self {
+            twist_pruning: pruning_table((&twist_moves, TWIST_COUNT, 0), slice, face_turns.len()),
+            flip_pruning: pruning_table((&flip_moves, FLIP_COUNT, 0), slice, face_turns.len()),
+            corner_pruning: pruning_table(
+                (&corner_permutation_moves, CORNER_PERMUTATION_COUNT, 0),
+                slice_permutation,
+                phase_two_moves.len(),
+            ),
+            edge_pruning: pruning_table(
+                (&edge_permutation_moves, EDGE_PERMUTATION_COUNT, 0),
+                slice_permutation,
+                phase_two_moves.len(),
+            ),
+            twist_moves,
+            flip_moves,
+            slice_moves,
+            corner_permutation_moves,
+            edge_permutation_moves,
+            slice_permutation_moves,
+            target_length: 0,
+        }
+    }
+
+    /// Stops as soon as a solution of at most `target_length` moves is found, instead of
+    /// searching for shorter ones until the time runs out.
+    pub fn with_target_length(mut self, target_length: usize) -> Self {
+        self.target_length = target_length;
+        self
+    }
+
+    /// Returns the shortest solution found in `time`, None if the cube cannot be solved by face
+    /// turns. The search goes on past `time` until a first solution is found.
+    pub fn solve(&self, model: &CubeModel, time: Duration) -> Option<Vec<Actions>> {
+        self.solve_with(model, time, |_| {})
+    }
+
+    /// Like `solve`, calling `on_solution` with every solution found, each shorter than the previous one.
+    pub fn solve_with(
+        &self,
+        model: &CubeModel,
+        time: Duration,
+        on_solution: impl FnMut(&[Actions]),
+    ) -> Option<Vec<Actions>> {
+        let cube = face_turn_cube(model)?;
+        let mut search = Search {
+            solver: self,
+            cube,
+            path: Vec::new(),
+            best: None,
+            deadline: Instant::now() + time,
+            done: false,
+            on_solution,
+        };
+        for depth in 0..=MAX_PHASE_ONE {
+            if search.done || search.best.as_ref().is_some_and(|best| best.len() <= depth) {
+                break;
+            }
+            search.phase_one(twist(&cube), flip(&cube), slice(&cube), depth);
+        }
+        search.best
+    }
+
+    fn phase_one_distance(&self, twist: usize, flip: usize, slice: usize) -> usize {
+        self.twist_pruning[twist * SLICE_COUNT + slice].max(self.flip_pruning[flip * SLICE_COUNT + slice]) as usize
+    }
+
+    fn phase_two_distance(&self, corners: usize, edges: usize, slice: usize) -> usize {
+        self.corner_pruning[corners * SLICE_PERMUTATION_COUNT + slice]
+            .max(self.edge_pruning[edges * SLICE_PERMUTATION_COUNT + slice]) as usize
+    }
+}
+
+struct Search<'a, F: FnMut(&[Actions])> {
+    solver: &'a TwoPhase,
+    cube: PackedCube,
+    path: Vec<Actions>,
+    best: Option<Vec<Actions>>,
+    deadline: Instant,
+    done: bool,
+    on_solution: F,
+}
+
+impl<F: FnMut(&[Actions])> Search<'_, F> {
+    fn phase_one(&mut self, twist: usize, flip: usize, slice: usize, remaining: usize) {
+        if self.done {
+            return;
+        }
+        let distance = self.solver.phase_one_distance(twist, flip, slice);
+        if distance > remaining {
+            return;
+        }
+        if remaining == 0 {
+            // ending with a phase two move means a shorter phase one solution was already tried
+            let last = self.path.last();
+            if last.is_none_or(|last| !PHASE_TWO_MOVES.contains(last)) {
+                self.start_phase_two();
+            }
+            return;
+        }
+        for (i, action) in Actions::FACE_TURNS.into_iter().enumerate() {
+            if !action.follows_canonically(self.path.last().copied()) {
+                continue;
+            }
+            self.path.push(action);
+            let moves = Actions::FACE_TURNS.len();
+            self.phase_one(
+                self.solver.twist_moves[twist * moves + i] as usize,
+                self.solver.flip_moves[flip * moves + i] as usize,
+                self.solver.slice_moves[slice * moves + i] as usize,
+                remaining - 1,
+            );
+            self.path.pop();
+            if self.done {
+                return;
+            }
+        }
+    }
+
+    fn start_phase_two(&mut self) {
+        let phase_one = self.path.len();
+        let max_length = match &self.best {
+            Some(best) => best.len() - 1,
+            None => phase_one + MAX_PHASE_TWO,
+        };
+        let cube = self.path.iter().fold(self.cube, |cube, action| cube.multiply(action.packed()));
+        let (corners, edges, slice) = (corner_permutation(&cube), edge_permutation(&cube), slice_permutation(&cube));
+        let distance = self.solver.phase_two_distance(corners, edges, slice);
+        for depth in distance..=max_length.saturating_sub(phase_one) {
+            if self.phase_two(corners, edges, slice, depth) {
+                let solution = self.path.clone();
+                self.path.truncate(phase_one);
+                (self.on_solution)(&solution);
+                self.done = solution.len() <= self.solver.target_length;
+                self.best = Some(solution);
+                break;
+            }
+        }
+        if self.best.is_some() && Instant::now() >= self.deadline {
+            self.done = true;
+        }
+    }
+
+    /// Leaves the solution on the path when one is found.
+    fn phase_two(&mut self, corners: usize, edges: usize, slice: usize, remaining: usize) -> bool {
+        let distance = self.solver.phase_two_distance(corners, edges, slice);
+        if distance > remaining {
+            return false;
+        }
+        if remaining == 0 {
+            return true;
+        }
+        let moves = PHASE_TWO_MOVES.len();
+        for (i, action) in PHASE_TWO_MOVES.into_iter().enumerate() {
+            if !action.follows_canonically(self.path.last().copied()) {
+                continue;
+            }
+            self.path.push(action);
+            if self.phase_two(
+                self.solver.corner_permutation_moves[corners * moves + i] as usize,
+                self.solver.edge_permutation_moves[edges * moves + i] as usize,
+                self.solver.slice_permutation_moves[slice * moves + i] as usize,
+                remaining - 1,
+            ) {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::{Actionable, Algorithm};
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+    use std::sync::OnceLock;
+
+    fn solver() -> &'static TwoPhase {
+        static SOLVER: OnceLock<TwoPhase> = OnceLock::new();
+        SOLVER.get_or_init(TwoPhase::new)
+    }
+
+    fn scrambled(scramble: &str) -> CubeModel {
+        let mut model = CubeModel::solved();
+        scramble.parse::<Algorithm>().unwrap().apply_to(&mut model);
+        model
+    }
+
+    fn assert_solves(model: &CubeModel, solution: &[Actions]) {
+        let mut model = model.clone();
+        Algorithm::new(solution.to_vec()).apply_to(&mut model);
+        assert!(model.is_solved());
+    }
+
+    #[test]
+    fn coordinates_follow_the_move_tables() {
+        let cube = PackedCube::try_from(&scrambled("R U F' L2 D B' R2")).unwrap();
+        let moves = Actions::FACE_TURNS.len();
+        for (i, action) in Actions::FACE_TURNS.into_iter().enumerate() {
+            let next = cube.multiply(action.packed());
+            assert_eq!(solver().twist_moves[twist(&cube) * moves + i] as usize, twist(&next));
+            assert_eq!(solver().flip_moves[flip(&cube) * moves + i] as usize, flip(&next));
+            assert_eq!(solver().slice_moves[slice(&cube) * moves + i] as usize, slice(&next));
+        }
+    }
+
+    #[test]
+    fn solved_cube() {
+        let solution = solver().solve(&CubeModel::solved(), Duration::ZERO).unwrap();
+        assert!(solution.is_empty());
+    }
+
+    #[test]
+    fn short_scramble_is_solved_optimally() {
+        let model = scrambled("R U F'");
+        let solution = solver().solve(&model, Duration::from_secs(60)).unwrap();
+        assert_eq!(Algorithm::new(solution.clone()).to_string(), "F U' R'");
+        assert_solves(&model, &solution);
+    }
+
+    #[test]
+    fn long_scramble() {
+        let scramble = "D2 F' U2 L' B R2 D' F2 U R' B2 L D2 F U' R2 B' L2 U F' D R B2 L' U2";
+        let model = scrambled(scramble);
+        let solution = solver().solve(&model, Duration::ZERO).unwrap();
+        assert!(solution.len() <= 30);
+        assert_solves(&model, &solution);
+    }
+
+    /// A long random scramble, leaving the cube in a random state about 18 moves from solved.
+    fn random_state() -> CubeModel {
+        let mut rng = StdRng::seed_from_u64(7);
+        let scramble = Algorithm::new((0..100).map(|_| *Actions::FACE_TURNS.choose(&mut rng).unwrap()).collect());
+        scrambled(&scramble.to_string())
+    }
+
+    #[test]
+    fn random_state_is_solved_near_optimally() {
+        let model = random_state();
+        // even the first solution found, shorter ones follow given time
+        let solution = solver().solve(&model, Duration::ZERO).unwrap();
+        assert!(solution.len() <= 24, "{} moves", solution.len());
+        assert_solves(&model, &solution);
+    }
+
+    #[test]
+    fn solutions_get_shorter() {
+        // the first solution of this state takes 23 moves, shorter ones are found within a second
+        let model = random_state();
+        let mut lengths = Vec::new();
+        let solution = solver()
+            .solve_with(&model, Duration::from_secs(1), |solution| lengths.push(solution.len()))
+            .unwrap();
+        assert!(lengths.len() >= 2, "{:?}", lengths);
+        assert!(lengths.windows(2).all(|pair| pair[1] < pair[0]));
+        assert_eq!(lengths.last(), Some(&solution.len()));
+        assert_solves(&model, &solution);
+    }
+
+    #[test]
+    fn unsolvable_cubes() {
+        let mut model = CubeModel::solved();
+        model.apply_action(Actions::M);
+        assert_eq!(solver().solve(&model, Duration::ZERO), None);
+
+        let model = scrambled("R U");
+        let mut orientations = *model.orientations();
+        orientations[2][2][0] = (orientations[2][2][0] + 1) % 3;
+        let model = CubeModel::new(*model.cube_elements(), orientations);
+        assert_eq!(solver().solve(&model, Duration::ZERO), None);
+    }
+}
@@ -0,0 +1,173 @@
+use super::action::Actions;
+use super::algorithm::{Algorithm, StagedSolution};
+use super::algos::IdaStar;
+use super::method::{edge_slot, with_pieces, Guide, Progress};
+use super::pattern_database::{Pattern, PatternDatabase};
+use super::two_phase::{flip, move_table, pruning_table};
+use crate::core::{Constraint, CubeModel, MaskedModel, PackedCube, TileColor};
+
+const FLIP_COUNT: usize = 2048;
+/// Slots of the DF and DB edges, the line.
+const LINE: [usize; 2] = [5, 7];
+const LINE_COUNT: usize = 132;
+const MOVE_COUNT: usize = Actions::FACE_TURNS.len();
+
+/// Squares at the back and pairs at the front of the left and right blocks, by the slots of
+/// their corners and edges.
+const LEFT_SQUARE: (&[usize], &[usize]) = (&[6], &[6, 10]);
+const LEFT_PAIR: (&[usize], &[usize]) = (&[5], &[9]);
+const RIGHT_SQUARE: (&[usize], &[usize]) = (&[7], &[4, 11]);
+const RIGHT_PAIR: (&[usize], &[usize]) = (&[4], &[8]);
+
+/// The moves of the first two layers, which keep the edges oriented and the line in place.
+const LEFT_BLOCK_ACTIONS: [Actions; 9] = [
+    Actions::R,
+    Actions::RC,
+    Actions::R2,
+    Actions::U,
+    Actions::UC,
+    Actions::U2,
+    Actions::L,
+    Actions::LC,
+    Actions::L2,
+];
+const RIGHT_BLOCK_ACTIONS: [Actions; 6] = [Actions::R, Actions::RC, Actions::R2, Actions::U, Actions::UC, Actions::U2];
+
+/// Slots of the DF and DB edges, whatever their orientation, the second one counted among the
+/// eleven slots left by the first.
+fn line(cube: &PackedCube) -> usize {
+    let (front, back) = (edge_slot(cube, LINE[0]), edge_slot(cube, LINE[1]));
+    front * 11 + back - usize::from(back > front)
+}
+
+/// Solves the cube with the ZZ method, holding white down: an optimal EOLine orienting all the
+/// edges and placing the DF and DB edges, after which the first two layers only need R, U and L
+/// turns, then a block on the left and one on the right, each as a square then a pair found by
+/// searches guided by pattern databases, and the last layer as in `Cfop`, the edges being
+/// already oriented.
+pub struct Zz {
+    flip_moves: Vec<u16>,
+    line_moves: Vec<u16>,
+    /// Exact EOLine distances by edge orientation and line.
+    eoline: Vec<u8>,
+    squares: [PatternDatabase; 2],
+    pairs: [PatternDatabase; 2],
+}
+
+impl Zz {
+    /// Generates the EOLine distances and the pattern databases of the blocks.
+    pub fn new() -> Self {
+        let moves = Actions::FACE_TURNS.map(|action| *action.packed());
+        let flip_moves = move_table(FLIP_COUNT, flip, &moves);
+        let line_moves = move_table(LINE_COUNT, line, &moves);
+        let solved_line = line(&PackedCube::SOLVED);
+        let eoline = pruning_table((&flip_moves, FLIP_COUNT, 0), (&line_moves, LINE_COUNT, solved_line), MOVE_COUNT);
+        let database = |(corners, edges): (&[usize], &[usize])| PatternDatabase::generate(Pattern::new(corners, edges));
+        Self {
+            flip_moves,
+            line_moves,
+            eoline,
+            squares: [database(LEFT_SQUARE), database(RIGHT_SQUARE)],
+            pairs: [database(LEFT_PAIR), database(RIGHT_PAIR)],
+        }
+    }
+
+    /// The optimal EOLine of the cube, following the moves that bring the exact distance down.
+    fn eoline(&self, cube: &PackedCube) -> Algorithm {
+        let (mut flip, mut line) = (flip(cube), line(cube));
+        let mut actions = Vec::new();
+        while self.eoline[flip * LINE_COUNT + line] > 0 {
+            let distance = self.eoline[flip * LINE_COUNT + line];
+            let (i, action) = Actions::FACE_TURNS
+                .iter()
+                .enumerate()
+                .find(|(i, _)| {
+                    let next = self.flip_moves[flip * MOVE_COUNT + i] as usize * LINE_COUNT + self.line_moves[line * MOVE_COUNT + i] as usize;
+                    self.eoline[next] < distance
+                })
+                .unwrap();
+            flip = self.flip_moves[flip * MOVE_COUNT + i] as usize;
+            line = self.line_moves[line * MOVE_COUNT + i] as usize;
+            actions.push(*action);
+        }
+        Algorithm::new(actions)
+    }
+
+    /// The stages `EOLine`, `Left block`, `Right block`, then the OLL and PLL named as by
+    /// `Cfop`. The EOLine starts with the rotation holding white down. None if the model cannot
+    /// be solved.
+    pub fn solve(&self, model: &CubeModel) -> Option<StagedSolution> {
+        let mut progress = Progress::new(model, TileColor::White)?;
+
+        let eoline = self.eoline(progress.cube());
+        progress.apply(&eoline);
+        progress.end_stage("EOLine");
+
+        let mut goal = MaskedModel::edge_orientation().with_edge(LINE[0], Constraint::Exact).with_edge(LINE[1], Constraint::Exact);
+        for (side, name, actions) in [(0, "Left block", &LEFT_BLOCK_ACTIONS[..]), (1, "Right block", &RIGHT_BLOCK_ACTIONS[..])] {
+            let (square, pair) = [(LEFT_SQUARE, LEFT_PAIR), (RIGHT_SQUARE, RIGHT_PAIR)][side];
+            goal = with_pieces(goal, square);
+            let mut guide = vec![&self.squares[side]];
+            progress.apply_search(&IdaStar::new(Guide(guide.clone())).with_actions(actions), &goal)?;
+            goal = with_pieces(goal, pair);
+            guide.push(&self.pairs[side]);
+            progress.apply_search(&IdaStar::new(Guide(guide)).with_actions(actions), &goal)?;
+            progress.end_stage(name);
+        }
+
+        progress.last_layer()?;
+
+        progress.finish()
+    }
+}
+
+impl Default for Zz {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::method::shortest;
+    use crate::agents::method::testing::{assert_solves, random_scramble};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::sync::OnceLock;
+
+    fn solver() -> &'static Zz {
+        static SOLVER: OnceLock<Zz> = OnceLock::new();
+        SOLVER.get_or_init(Zz::new)
+    }
+
+    #[test]
+    fn optimal_eoline() {
+        let goal = MaskedModel::edge_orientation().with_edge(LINE[0], Constraint::Exact).with_edge(LINE[1], Constraint::Exact);
+        let moves = Actions::FACE_TURNS.map(|action| Algorithm::new(vec![action]));
+        for scramble in ["", "F", "F B", "R F U' D2 B", "L' B R2 F' D"] {
+            let mut cube = PackedCube::SOLVED;
+            scramble.parse::<Algorithm>().unwrap().apply_to(&mut cube);
+            let eoline = solver().eoline(&cube);
+            assert_eq!(eoline.len(), shortest(&cube, &moves, &goal, 5).unwrap().len(), "{}", scramble);
+            eoline.apply_to(&mut cube);
+            assert!(goal.matches_packed(&cube));
+            assert_eq!(CubeModel::from(cube).bad_edges(), 0);
+        }
+    }
+
+    #[test]
+    fn solves_scrambles() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..3 {
+            let model = random_scramble(&mut rng, 25);
+            let solution = solver().solve(&model).unwrap();
+            // the first two layers keep the edges oriented
+            let (_, left_block) = &solution.stages()[1];
+            assert!(left_block.actions().iter().all(|action| LEFT_BLOCK_ACTIONS.contains(action)));
+            let (_, right_block) = &solution.stages()[2];
+            assert!(right_block.actions().iter().all(|action| RIGHT_BLOCK_ACTIONS.contains(action)));
+            assert!(matches!(solution.stages()[3].0.as_str(), "OLL skip" | "OLL 21" | "OLL 22" | "OLL 23" | "OLL 24" | "OLL 25" | "OLL 26" | "OLL 27"));
+            assert_solves(&solution, &model);
+        }
+    }
+}
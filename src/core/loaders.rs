@@ -1,8 +1,95 @@
-use crate::core::{CubeElement, TileColor};
-use std::{fs::read_to_string, path::Path};
+use crate::core::{slot_faces, CubeElement, CubeModel, Face, TileColor};
+use std::{error::Error, fmt::Display, fs::read_to_string, io, path::Path};
 
 pub trait CubeLoader {
-    fn to_model_elements(self) -> [[[CubeElement; 3]; 3]; 3];
+    fn to_model(self) -> Result<CubeModel, LoadError>;
+}
+
+/// Location of a tile in the loaded text, as 1-based line and column.
+pub type TileLocation = (usize, usize);
+
+/// Reasons why a cube could not be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// The character is not one of the tile colors.
+    InvalidColor { line: usize, column: usize, found: char },
+    /// The text ends before all the face rows are read.
+    MissingLines { expected: usize, found: usize },
+    /// There is text after all the face rows are read.
+    TrailingLine { line: usize },
+    /// The row has fewer tiles than a face row.
+    MissingTiles { line: usize, expected: usize, found: usize },
+    /// The row has more tiles than a face row, the first extra one is reported.
+    ExtraTile { line: usize, column: usize },
+    /// Every color must appear on exactly 9 tiles.
+    WrongColorCount { color: TileColor, count: usize },
+    /// The colors of the tiles do not form an existing corner.
+    InvalidCorner { tiles: [TileLocation; 3], colors: [TileColor; 3] },
+    /// The colors of the tiles do not form an existing edge.
+    InvalidEdge { tiles: [TileLocation; 2], colors: [TileColor; 2] },
+    /// The same element is found in more than one slot.
+    DuplicateElement { element: CubeElement },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "cannot read cube: {}", err),
+            LoadError::InvalidColor { line, column, found } => {
+                write!(f, "line {}, column {}: invalid tile color '{}'", line, column, found)
+            }
+            LoadError::MissingLines { expected, found } => {
+                write!(f, "expected {} lines of tiles, found {}", expected, found)
+            }
+            LoadError::TrailingLine { line } => write!(f, "line {}: unexpected text after the last face", line),
+            LoadError::MissingTiles { line, expected, found } => {
+                write!(f, "line {}: expected {} tiles, found {}", line, expected, found)
+            }
+            LoadError::ExtraTile { line, column } => {
+                write!(f, "line {}, column {}: unexpected tile after the end of the row", line, column)
+            }
+            LoadError::WrongColorCount { color, count } => {
+                write!(f, "expected 9 {:?} tiles, found {}", color, count)
+            }
+            LoadError::InvalidCorner { tiles, colors } => write!(
+                f,
+                "impossible corner {:?} at {}",
+                colors,
+                format_locations(tiles)
+            ),
+            LoadError::InvalidEdge { tiles, colors } => write!(
+                f,
+                "impossible edge {:?} at {}",
+                colors,
+                format_locations(tiles)
+            ),
+            LoadError::DuplicateElement { element } => write!(f, "element {} found more than once", element),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+fn format_locations(locations: &[TileLocation]) -> String {
+    locations
+        .iter()
+        .map(|(line, column)| format!("line {} column {}", line, column))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// This enum is used in the hand-crafter map of tiles to CubeElements.
@@ -24,7 +111,7 @@ impl CubeElementType {
         }
     }
 
-    fn get_sorted_tiles(&self, tiles: &Vec<TileColor>) -> Vec<TileColor> {
+    fn get_sorted_tiles(&self, tiles: &[TileColor]) -> Vec<TileColor> {
         let mut sorted_tiles = self
             .get_tiles_indexes()
             .iter()
@@ -34,11 +121,15 @@ impl CubeElementType {
         sorted_tiles
     }
 
-    fn compose_element(&self, tiles: &Vec<TileColor>) -> CubeElement {
-        let tiles = self.get_sorted_tiles(tiles);
+    fn compose_element(
+        &self,
+        tiles: &[TileColor],
+        locations: &[TileLocation],
+    ) -> Result<CubeElement, LoadError> {
+        let sorted_tiles = self.get_sorted_tiles(tiles);
 
-        if let Self::Vertix(_, _, _) = self {
-            match tiles.as_slice() {
+        let element = if let Self::Vertix(a, b, c) = self {
+            match sorted_tiles.as_slice() {
                 [TileColor::White, TileColor::Orange, TileColor::Green] => {
                     CubeElement::WhiteOrangeGreen
                 }
@@ -57,10 +148,15 @@ impl CubeElementType {
                     CubeElement::YellowGreenRed
                 }
                 [TileColor::Red, TileColor::Blue, TileColor::Yellow] => CubeElement::YellowRedBlue,
-                _ => panic!("Invalid vertix"),
+                _ => {
+                    return Err(LoadError::InvalidCorner {
+                        tiles: [locations[*a], locations[*b], locations[*c]],
+                        colors: [tiles[*a], tiles[*b], tiles[*c]],
+                    })
+                }
             }
-        } else if let Self::Edge(_, _) = self {
-            match tiles.as_slice() {
+        } else if let Self::Edge(a, b) = self {
+            match sorted_tiles.as_slice() {
                 [TileColor::White, TileColor::Orange] => CubeElement::WhiteOrange,
                 [TileColor::White, TileColor::Green] => CubeElement::WhiteGreen,
                 [TileColor::White, TileColor::Red] => CubeElement::WhiteRed,
@@ -73,24 +169,73 @@ impl CubeElementType {
                 [TileColor::Green, TileColor::Red] => CubeElement::RedGreen,
                 [TileColor::Orange, TileColor::Blue] => CubeElement::OrangeBlue,
                 [TileColor::Orange, TileColor::Green] => CubeElement::OrangeGreen,
-                _ => panic!("Invalid edge"),
+                _ => {
+                    return Err(LoadError::InvalidEdge {
+                        tiles: [locations[*a], locations[*b]],
+                        colors: [tiles[*a], tiles[*b]],
+                    })
+                }
             }
         } else if let Self::Face(_) = self {
-            match tiles.as_slice() {
+            match sorted_tiles.as_slice() {
                 [TileColor::White] => CubeElement::White,
                 [TileColor::Yellow] => CubeElement::Yellow,
                 [TileColor::Red] => CubeElement::Red,
                 [TileColor::Green] => CubeElement::Green,
                 [TileColor::Blue] => CubeElement::Blue,
                 [TileColor::Orange] => CubeElement::Orange,
-                _ => panic!("Invalid face"),
+                _ => unreachable!("a face has a single tile"),
             }
         } else {
             CubeElement::Kernel
+        };
+        Ok(element)
+    }
+
+    /// Orientation of `element` in the slot at `position`, read from the face its first color looks at.
+    /// The colors of a corner must also turn the same way round as in its home slot, otherwise
+    /// the corner is the mirror image of a real one.
+    fn compose_orientation(
+        &self,
+        element: CubeElement,
+        tiles: &[TileColor],
+        locations: &[TileLocation],
+        position: (usize, usize, usize),
+    ) -> Result<u8, LoadError> {
+        if let Self::Vertix(_, _, _) | Self::Edge(_, _) = self {
+            let indexes = self.get_tiles_indexes();
+            // the colors in the order of the faces of the slot
+            let colors: Vec<_> = slot_faces(position)
+                .iter()
+                .map(|face| tiles[*indexes.iter().find(|i| CTL_FACES[**i / 9] == *face).unwrap()])
+                .collect();
+            let oriented = element.oriented_colors();
+            let orientation = colors.iter().position(|color| *color == oriented[0]).unwrap();
+            if let Self::Vertix(a, b, c) = self {
+                if colors[(orientation + 1) % 3] != oriented[1] {
+                    return Err(LoadError::InvalidCorner {
+                        tiles: [locations[*a], locations[*b], locations[*c]],
+                        colors: [tiles[*a], tiles[*b], tiles[*c]],
+                    });
+                }
+            }
+            Ok(orientation as u8)
+        } else {
+            Ok(0)
         }
     }
 }
 
+/// Faces described by each block of 9 tiles in the text format, in order.
+const CTL_FACES: [Face; 6] = [
+    Face::Front,
+    Face::Right,
+    Face::Down,
+    Face::Back,
+    Face::Left,
+    Face::Up,
+];
+
 /// CubeTextLoader map of elements and indexes of tile array.
 const CTL_MAP: [[[CubeElementType; 3]; 3]; 3] = [
     [
@@ -170,42 +315,271 @@ pub struct CubeTextLoader {
 /// W W W
 /// W W W
 /// W W W
+/// Tiles of a row can also be written without spaces, as in `BBB`, and blank lines are ignored.
 impl CubeLoader for CubeTextLoader {
-    fn to_model_elements(self) -> [[[CubeElement; 3]; 3]; 3] {
-        let mut tile_colors = Vec::with_capacity(54);
-        let mut lines = self.text.lines();
-
-        // from formatted text to tile_colors 1D array
-        for _ in 0..6 {
-            for _ in 0..3 {
-                let line = lines.next().unwrap().trim();
-                for _ in 0..3 {
-                    let tile_color = match line.chars().next().unwrap() {
-                        'B' => TileColor::Blue,
-                        'O' => TileColor::Orange,
-                        'Y' => TileColor::Yellow,
-                        'G' => TileColor::Green,
-                        'R' => TileColor::Red,
-                        'W' => TileColor::White,
-                        _ => panic!("Invalid tile color"),
-                    };
-                    tile_colors.push(tile_color);
+    fn to_model(self) -> Result<CubeModel, LoadError> {
+        let (tile_colors, locations) = self.read_tiles()?;
+        compose_model(&tile_colors, &locations)
+    }
+}
+
+/// Builds the model from the 54 tiles listed in the order of the text format.
+fn compose_model(tile_colors: &[TileColor], locations: &[TileLocation]) -> Result<CubeModel, LoadError> {
+    for color in [
+        TileColor::White,
+        TileColor::Orange,
+        TileColor::Green,
+        TileColor::Red,
+        TileColor::Blue,
+        TileColor::Yellow,
+    ] {
+        let count = tile_colors.iter().filter(|c| **c == color).count();
+        if count != 9 {
+            return Err(LoadError::WrongColorCount { color, count });
+        }
+    }
+
+    let mut cube_elements = [[[CubeElement::Kernel; 3]; 3]; 3];
+    let mut orientations = [[[0; 3]; 3]; 3];
+    let mut composed = Vec::with_capacity(27);
+    for (x, plane) in CTL_MAP.iter().enumerate() {
+        for (y, row) in plane.iter().enumerate() {
+            for (z, element_type) in row.iter().enumerate() {
+                let element = element_type.compose_element(tile_colors, locations)?;
+                if composed.contains(&element) {
+                    return Err(LoadError::DuplicateElement { element });
                 }
+                composed.push(element);
+                cube_elements[x][y][z] = element;
+                orientations[x][y][z] =
+                    element_type.compose_orientation(element, tile_colors, locations, (x, y, z))?;
             }
         }
+    }
+    Ok(CubeModel::new(cube_elements, orientations))
+}
 
-        CTL_MAP.map(|x| x.map(|y| y.map(|z| z.compose_element(&tile_colors))))
+/// Slot and face of every tile of the text format, in order.
+fn ctl_tiles() -> [((usize, usize, usize), Face); 54] {
+    let mut tiles = [((0, 0, 0), Face::Up); 54];
+    for (x, plane) in CTL_MAP.iter().enumerate() {
+        for (y, row) in plane.iter().enumerate() {
+            for (z, element_type) in row.iter().enumerate() {
+                for index in element_type.get_tiles_indexes() {
+                    tiles[index] = ((x, y, z), CTL_FACES[index / 9]);
+                }
+            }
+        }
     }
+    tiles
 }
 
 impl CubeTextLoader {
-    fn from_string(text: String) -> Self {
+    /// Reads the 54 tiles in order, along with where each of them is found in the text.
+    fn read_tiles(&self) -> Result<(Vec<TileColor>, Vec<TileLocation>), LoadError> {
+        let mut tile_colors = Vec::with_capacity(54);
+        let mut locations = Vec::with_capacity(54);
+        // blank lines are skipped, line numbers still refer to the original text
+        let mut lines = self
+            .text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        for found in 0..18 {
+            let (line_number, line) = lines.next().ok_or(LoadError::MissingLines {
+                expected: 18,
+                found,
+            })?;
+            // every non blank character is a tile, so rows may or may not be spaced
+            let mut chars = line
+                .chars()
+                .enumerate()
+                .map(|(i, c)| (i + 1, c))
+                .filter(|(_, c)| !c.is_whitespace());
+            for found in 0..3 {
+                let (column, c) = chars.next().ok_or(LoadError::MissingTiles {
+                    line: line_number,
+                    expected: 3,
+                    found,
+                })?;
+                let tile_color = match c {
+                    'B' => TileColor::Blue,
+                    'O' => TileColor::Orange,
+                    'Y' => TileColor::Yellow,
+                    'G' => TileColor::Green,
+                    'R' => TileColor::Red,
+                    'W' => TileColor::White,
+                    _ => {
+                        return Err(LoadError::InvalidColor {
+                            line: line_number,
+                            column,
+                            found: c,
+                        })
+                    }
+                };
+                tile_colors.push(tile_color);
+                locations.push((line_number, column));
+            }
+            if let Some((column, _)) = chars.next() {
+                return Err(LoadError::ExtraTile {
+                    line: line_number,
+                    column,
+                });
+            }
+        }
+        if let Some((line_number, _)) = lines.next() {
+            return Err(LoadError::TrailingLine { line: line_number });
+        }
+        Ok((tile_colors, locations))
+    }
+
+    pub fn from_string(text: String) -> Self {
         Self { text }
     }
 
-    pub fn from_file(path: &Path) -> Self {
-        let text = read_to_string(path).unwrap();
-        CubeTextLoader { text }
+    pub fn from_file(path: &Path) -> Result<Self, LoadError> {
+        let text = read_to_string(path)?;
+        Ok(CubeTextLoader { text })
+    }
+
+    /// Writes the model in the same format read by the loader, one face row per line.
+    pub fn export(model: &CubeModel) -> String {
+        let tile_colors = ctl_tiles().map(|(position, face)| model.tile_color(position, face));
+
+        let mut text = String::with_capacity(72);
+        for line in tile_colors.chunks(3) {
+            for tile_color in line {
+                let c = match tile_color {
+                    TileColor::Blue => 'B',
+                    TileColor::Orange => 'O',
+                    TileColor::Yellow => 'Y',
+                    TileColor::Green => 'G',
+                    TileColor::Red => 'R',
+                    TileColor::White => 'W',
+                };
+                text.push(c);
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Faces in the order they are listed in a facelet string.
+const FACELET_FACES: [Face; 6] = [
+    Face::Up,
+    Face::Right,
+    Face::Front,
+    Face::Down,
+    Face::Left,
+    Face::Back,
+];
+
+/// FaceletLoader is a loader for a cube model from the 54 characters facelet string used by
+/// Kociemba's solver and most cube tools, e.g. for the solved cube:
+/// UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB
+/// Faces are listed in the order U, R, F, D, L, B and each face is read row by row as seen from
+/// outside the cube, with the U face above F and R and the D face below them.
+/// Each facelet is the name of the face whose center has its color on the solved cube.
+/// Whitespace is ignored, errors report the column of the facelet on line 1.
+pub struct FaceletLoader {
+    facelets: String,
+}
+
+impl FaceletLoader {
+    pub fn from_string(facelets: String) -> Self {
+        Self { facelets }
+    }
+
+    /// Slot of the facelet at `row` and `column` of `face`, both starting from 0.
+    fn facelet_position(face: Face, row: usize, column: usize) -> (usize, usize, usize) {
+        match face {
+            Face::Up => (column, 2, 2 - row),
+            Face::Right => (2, 2 - row, column),
+            Face::Front => (column, 2 - row, 0),
+            Face::Down => (column, 0, row),
+            Face::Left => (0, 2 - row, 2 - column),
+            Face::Back => (2 - column, 2 - row, 2),
+        }
+    }
+
+    /// Slot and face of every facelet, in order.
+    fn facelet_tiles() -> [((usize, usize, usize), Face); 54] {
+        let mut tiles = [((0, 0, 0), Face::Up); 54];
+        for (i, tile) in tiles.iter_mut().enumerate() {
+            let face = FACELET_FACES[i / 9];
+            *tile = (Self::facelet_position(face, (i % 9) / 3, i % 3), face);
+        }
+        tiles
+    }
+
+    fn face_letter(face: Face) -> char {
+        match face {
+            Face::Up => 'U',
+            Face::Right => 'R',
+            Face::Front => 'F',
+            Face::Down => 'D',
+            Face::Left => 'L',
+            Face::Back => 'B',
+        }
+    }
+
+    /// Writes the model as a facelet string.
+    pub fn export(model: &CubeModel) -> String {
+        Self::facelet_tiles()
+            .iter()
+            .map(|(position, face)| {
+                let color = model.tile_color(*position, *face);
+                let home = FACELET_FACES.into_iter().find(|f| f.color() == color).unwrap();
+                Self::face_letter(home)
+            })
+            .collect()
+    }
+}
+
+impl CubeLoader for FaceletLoader {
+    fn to_model(self) -> Result<CubeModel, LoadError> {
+        let mut chars = self
+            .facelets
+            .chars()
+            .enumerate()
+            .map(|(i, c)| (i + 1, c))
+            .filter(|(_, c)| !c.is_whitespace());
+        let mut facelet_colors = Vec::with_capacity(54);
+        let mut facelet_locations = Vec::with_capacity(54);
+        for found in 0..54 {
+            let (column, c) = chars.next().ok_or(LoadError::MissingTiles {
+                line: 1,
+                expected: 54,
+                found,
+            })?;
+            let face = FACELET_FACES
+                .into_iter()
+                .find(|f| Self::face_letter(*f) == c)
+                .ok_or(LoadError::InvalidColor {
+                    line: 1,
+                    column,
+                    found: c,
+                })?;
+            facelet_colors.push(face.color());
+            facelet_locations.push((1, column));
+        }
+        if let Some((column, _)) = chars.next() {
+            return Err(LoadError::ExtraTile { line: 1, column });
+        }
+
+        // reorder the facelets as the tiles of the text format
+        let facelet_tiles = Self::facelet_tiles();
+        let mut tile_colors = Vec::with_capacity(54);
+        let mut locations = Vec::with_capacity(54);
+        for tile in ctl_tiles() {
+            let i = facelet_tiles.iter().position(|t| *t == tile).unwrap();
+            tile_colors.push(facelet_colors[i]);
+            locations.push(facelet_locations[i]);
+        }
+        compose_model(&tile_colors, &locations)
     }
 }
 
@@ -214,13 +588,29 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    fn solved_cube_path() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join("solved_cube.txt")
+    }
+
     fn load_solved_cube_elems() -> [[[CubeElement; 3]; 3]; 3] {
         // get the path of the file
-        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-            .join("assets")
-            .join("solved_cube.txt");
-        let loader = CubeTextLoader::from_file(path.as_path());
-        loader.to_model_elements()
+        let path = solved_cube_path();
+        let loader = CubeTextLoader::from_file(path.as_path()).unwrap();
+        *loader.to_model().unwrap().cube_elements()
+    }
+
+    fn solved_cube_lines() -> Vec<String> {
+        read_to_string(solved_cube_path())
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    fn load_lines(lines: &[String]) -> Result<CubeModel, LoadError> {
+        CubeTextLoader::from_string(lines.join("\n")).to_model()
     }
 
     #[test]
@@ -254,4 +644,281 @@ mod tests {
         assert_eq!(cube_elems[2][2][1], CubeElement::WhiteOrange);
         assert_eq!(cube_elems[2][2][2], CubeElement::WhiteOrangeGreen);
     }
+
+    #[test]
+    fn solved_cube_exported_as_file() {
+        let text = read_to_string(solved_cube_path()).unwrap();
+        let exported = CubeTextLoader::export(&CubeModel::solved());
+        assert_eq!(exported.lines().collect::<Vec<_>>(), text.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn scrambled_cube_round_trip() {
+        let mut model = CubeModel::solved();
+        model.right_clockwise();
+        model.up_clockwise();
+        model.front_counter_clockwise();
+        model.left_clockwise();
+        model.back_clockwise();
+        model.down_counter_clockwise();
+        let exported = CubeTextLoader::export(&model);
+        let loaded = CubeTextLoader::from_string(exported.clone()).to_model().unwrap();
+        assert_eq!(loaded, model);
+        assert_eq!(CubeTextLoader::export(&loaded), exported);
+    }
+
+    #[test]
+    fn twisted_corner_keeps_orientation() {
+        // the white tile of the up-right-front corner looks at the right face
+        let mut lines = solved_cube_lines();
+        lines[0].replace_range(2..3, "O");
+        lines[3].replace_range(0..1, "W");
+        lines[17].replace_range(2..3, "B");
+        let model = load_lines(&lines).unwrap();
+        assert_eq!(model.cube_elements()[2][2][0], CubeElement::WhiteBlueOrange);
+        assert_eq!(model.orientations()[2][2][0], 1);
+        assert!(!model.is_solved());
+    }
+
+    #[test]
+    fn mirrored_corner() {
+        // swap two tiles of the up-right-front corner, its colors then turn the wrong way round
+        let mut lines = solved_cube_lines();
+        let (first, second) = (lines[0][2..3].to_string(), lines[3][0..1].to_string());
+        lines[0].replace_range(2..3, &second);
+        lines[3].replace_range(0..1, &first);
+        match load_lines(&lines) {
+            Err(LoadError::InvalidCorner { tiles, .. }) => {
+                assert!(tiles.contains(&(1, 3)) && tiles.contains(&(4, 1)));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_file() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join("missing_cube.txt");
+        assert!(matches!(CubeTextLoader::from_file(path.as_path()), Err(LoadError::Io(_))));
+    }
+
+    #[test]
+    fn invalid_color() {
+        let mut lines = solved_cube_lines();
+        lines[4].replace_range(1..2, "X");
+        assert!(matches!(
+            load_lines(&lines),
+            Err(LoadError::InvalidColor { line: 5, column: 2, found: 'X' })
+        ));
+    }
+
+    #[test]
+    fn missing_lines() {
+        let lines = solved_cube_lines();
+        assert!(matches!(
+            load_lines(&lines[..17]),
+            Err(LoadError::MissingLines { expected: 18, found: 17 })
+        ));
+    }
+
+    #[test]
+    fn missing_tiles() {
+        let mut lines = solved_cube_lines();
+        lines[2] = String::from("BB");
+        assert!(matches!(
+            load_lines(&lines),
+            Err(LoadError::MissingTiles { line: 3, expected: 3, found: 2 })
+        ));
+    }
+
+    #[test]
+    fn extra_tile() {
+        let mut lines = solved_cube_lines();
+        lines[0] = String::from("BBBB");
+        assert!(matches!(
+            load_lines(&lines),
+            Err(LoadError::ExtraTile { line: 1, column: 4 })
+        ));
+    }
+
+    #[test]
+    fn trailing_line() {
+        let mut lines = solved_cube_lines();
+        lines.push(String::from("WWW"));
+        assert!(matches!(load_lines(&lines), Err(LoadError::TrailingLine { line: 19 })));
+    }
+
+    #[test]
+    fn wrong_color_count() {
+        let mut lines = solved_cube_lines();
+        lines[0].replace_range(0..1, "O");
+        assert!(matches!(
+            load_lines(&lines),
+            Err(LoadError::WrongColorCount { color: TileColor::Orange, count: 10 })
+        ));
+    }
+
+    #[test]
+    fn red_orange_edge() {
+        // swap the white tile of the up-left edge with the orange tile of the up-right edge
+        let mut lines = solved_cube_lines();
+        lines[16].replace_range(0..1, "O");
+        lines[3].replace_range(1..2, "W");
+        match load_lines(&lines) {
+            Err(LoadError::InvalidEdge { tiles, colors }) => {
+                assert_eq!(tiles, [(14, 3), (17, 1)]);
+                assert_eq!(colors, [TileColor::Red, TileColor::Orange]);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn error_messages() {
+        let mut lines = solved_cube_lines();
+        lines[4].replace_range(1..2, "X");
+        let err = load_lines(&lines).unwrap_err();
+        assert_eq!(err.to_string(), "line 5, column 2: invalid tile color 'X'");
+    }
+
+    mod scrambled {
+        use super::*;
+
+        fn load_text(text: &str) -> CubeModel {
+            CubeTextLoader::from_string(String::from(text)).to_model().unwrap()
+        }
+
+        #[test]
+        fn right_clockwise_net() {
+            let model = load_text(
+                "BBY\nBBY\nBBY\nOOO\nOOO\nOOO\nGGG\nYYY\nYYY\n\
+                 WWW\nGGG\nGGG\nRRR\nRRR\nRRR\nWWB\nWWB\nWWB",
+            );
+            let mut expected = CubeModel::solved();
+            expected.right_clockwise();
+            assert_eq!(model, expected);
+        }
+
+        #[test]
+        fn up_clockwise_spaced_net() {
+            let model = load_text(
+                "O O O\nB B B\nB B B\n\nG G G\nO O O\nO O O\n\nY Y Y\nY Y Y\nY Y Y\n\n\
+                 G G R\nG G R\nG G R\n\nR R B\nR R B\nR R B\n\nW W W\nW W W\nW W W\n",
+            );
+            let mut expected = CubeModel::solved();
+            expected.up_clockwise();
+            assert_eq!(model, expected);
+        }
+
+        #[test]
+        fn front_counter_clockwise_net() {
+            let model = load_text(
+                "BBB\nBBB\nBBB\nYOO\nYOO\nYOO\nRYY\nRYY\nRYY\n\
+                 GGG\nGGG\nGGG\nRRR\nRRR\nWWW\nWWW\nWWW\nOOO",
+            );
+            let mut expected = CubeModel::solved();
+            expected.front_counter_clockwise();
+            assert_eq!(model, expected);
+        }
+
+        #[test]
+        fn unsolved_cube_loaded_from_file() {
+            // R U R' U' F F L D' B L L U
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("assets")
+                .join("unsolved_cube.txt");
+            let model = CubeTextLoader::from_file(path.as_path())
+                .unwrap()
+                .to_model()
+                .unwrap();
+            let mut expected = CubeModel::solved();
+            expected.right_clockwise();
+            expected.up_clockwise();
+            expected.right_counter_clockwise();
+            expected.up_counter_clockwise();
+            expected.front_clockwise();
+            expected.front_clockwise();
+            expected.left_clockwise();
+            expected.down_counter_clockwise();
+            expected.back_clockwise();
+            expected.left_clockwise();
+            expected.left_clockwise();
+            expected.up_clockwise();
+            assert_eq!(model, expected);
+        }
+    }
+
+    mod facelets {
+        use super::*;
+        use crate::core::{CORNER_SLOTS, EDGE_SLOTS};
+
+        const SOLVED: &str = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+
+        fn load_facelets(facelets: &str) -> Result<CubeModel, LoadError> {
+            FaceletLoader::from_string(String::from(facelets)).to_model()
+        }
+
+        #[test]
+        fn solved_cube() {
+            assert_eq!(load_facelets(SOLVED).unwrap(), CubeModel::solved());
+            assert_eq!(FaceletLoader::export(&CubeModel::solved()), SOLVED);
+        }
+
+        #[test]
+        fn right_clockwise() {
+            let facelets = "UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB";
+            let mut expected = CubeModel::solved();
+            expected.right_clockwise();
+            assert_eq!(load_facelets(facelets).unwrap(), expected);
+            assert_eq!(FaceletLoader::export(&expected), facelets);
+        }
+
+        #[test]
+        fn superflip() {
+            let model =
+                load_facelets("UBULURUFURURFRBRDRFUFLFRFDFDFDLDRDBDLULBLFLDLBUBRBLBDB").unwrap();
+            for ((x, y, z), _) in EDGE_SLOTS {
+                assert_eq!(model.cube_elements()[x][y][z], CubeModel::solved().cube_elements()[x][y][z]);
+                assert_eq!(model.orientations()[x][y][z], 1);
+            }
+            for ((x, y, z), _) in CORNER_SLOTS {
+                assert_eq!(model.orientations()[x][y][z], 0);
+            }
+        }
+
+        #[test]
+        fn round_trip_with_text_format() {
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("assets")
+                .join("unsolved_cube.txt");
+            let model = CubeTextLoader::from_file(path.as_path())
+                .unwrap()
+                .to_model()
+                .unwrap();
+            let facelets = FaceletLoader::export(&model);
+            assert_eq!(load_facelets(&facelets).unwrap(), model);
+        }
+
+        #[test]
+        fn wrong_length() {
+            assert!(matches!(
+                load_facelets(&SOLVED[..53]),
+                Err(LoadError::MissingTiles { line: 1, expected: 54, found: 53 })
+            ));
+            assert!(matches!(
+                load_facelets(&format!("{}U", SOLVED)),
+                Err(LoadError::ExtraTile { line: 1, column: 55 })
+            ));
+        }
+
+        #[test]
+        fn invalid_facelet() {
+            let facelets = SOLVED.replacen('R', "X", 1);
+            assert!(matches!(
+                load_facelets(&facelets),
+                Err(LoadError::InvalidColor { line: 1, column: 10, found: 'X' })
+            ));
+        }
+    }
 }
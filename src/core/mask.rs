@@ -0,0 +1,211 @@
+use super::model::{CubeModel, Face, CORNER_SLOTS, EDGE_SLOTS};
+use super::packed::PackedCube;
+use super::validation::ValidationError;
+
+/// What a `MaskedModel` requires of the piece in a slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Constraint {
+    /// Any piece, in any orientation.
+    Any,
+    /// Any piece, oriented as in the target.
+    Orientation,
+    /// The piece of the target, in any orientation.
+    Placed,
+    /// The piece of the target, oriented as in the target.
+    Exact,
+}
+
+impl Constraint {
+    fn accepts(self, (piece, orientation): (u8, u8), (target_piece, target_orientation): (u8, u8)) -> bool {
+        let placed = piece == target_piece;
+        let oriented = orientation == target_orientation;
+        match self {
+            Constraint::Any => true,
+            Constraint::Orientation => oriented,
+            Constraint::Placed => placed,
+            Constraint::Exact => placed && oriented,
+        }
+    }
+}
+
+/// A partial goal: a target cube with a constraint on every corner and edge slot, the other
+/// slots being wildcards, e.g. "cross solved" or "edges oriented". A cube matches when its
+/// centers are those of the target and every constraint holds.
+/// Slots are numbered as in `CORNER_SLOTS` and `EDGE_SLOTS`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MaskedModel {
+    target: PackedCube,
+    corners: [Constraint; 8],
+    edges: [Constraint; 12],
+}
+
+impl MaskedModel {
+    /// A mask of `target` where every slot is a wildcard, to be narrowed with `with_corner` and
+    /// `with_edge`.
+    pub fn new(target: &CubeModel) -> Result<Self, ValidationError> {
+        Ok(Self {
+            target: PackedCube::try_from(target)?,
+            corners: [Constraint::Any; 8],
+            edges: [Constraint::Any; 12],
+        })
+    }
+
+    /// Only matches the target itself.
+    pub fn exact(target: &CubeModel) -> Result<Self, ValidationError> {
+        Ok(Self::new(target)?.with_all(Constraint::Exact))
+    }
+
+    /// A mask of the solved cube where every slot is a wildcard.
+    pub fn solved() -> Self {
+        Self {
+            target: PackedCube::SOLVED,
+            corners: [Constraint::Any; 8],
+            edges: [Constraint::Any; 12],
+        }
+    }
+
+    /// The edges of `face` solved, e.g. the white cross for `Face::Up`.
+    pub fn cross(face: Face) -> Self {
+        Self::solved().with_edges_on(face, Constraint::Exact)
+    }
+
+    /// The corners and edges of `face` solved.
+    pub fn layer(face: Face) -> Self {
+        Self::cross(face).with_corners_on(face, Constraint::Exact)
+    }
+
+    /// The layer of `face` and the middle layer next to it solved.
+    pub fn first_two_layers(face: Face) -> Self {
+        let mut mask = Self::layer(face);
+        for (slot, (_, faces)) in EDGE_SLOTS.iter().enumerate() {
+            if !faces.contains(&face.opposite()) {
+                mask.edges[slot] = Constraint::Exact;
+            }
+        }
+        mask
+    }
+
+    /// Every edge oriented, wherever it is.
+    pub fn edge_orientation() -> Self {
+        let mut mask = Self::solved();
+        mask.edges = [Constraint::Orientation; 12];
+        mask
+    }
+
+    pub fn with_corner(mut self, slot: usize, constraint: Constraint) -> Self {
+        self.corners[slot] = constraint;
+        self
+    }
+
+    pub fn with_edge(mut self, slot: usize, constraint: Constraint) -> Self {
+        self.edges[slot] = constraint;
+        self
+    }
+
+    /// Sets the constraint of the corners of `face`.
+    pub fn with_corners_on(mut self, face: Face, constraint: Constraint) -> Self {
+        for (slot, (_, faces)) in CORNER_SLOTS.iter().enumerate() {
+            if faces.contains(&face) {
+                self.corners[slot] = constraint;
+            }
+        }
+        self
+    }
+
+    /// Sets the constraint of the edges of `face`.
+    pub fn with_edges_on(mut self, face: Face, constraint: Constraint) -> Self {
+        for (slot, (_, faces)) in EDGE_SLOTS.iter().enumerate() {
+            if faces.contains(&face) {
+                self.edges[slot] = constraint;
+            }
+        }
+        self
+    }
+
+    /// Sets the constraint of every slot.
+    pub fn with_all(mut self, constraint: Constraint) -> Self {
+        self.corners = [constraint; 8];
+        self.edges = [constraint; 12];
+        self
+    }
+
+    pub fn target(&self) -> &PackedCube {
+        &self.target
+    }
+
+    pub fn corner_constraint(&self, slot: usize) -> Constraint {
+        self.corners[slot]
+    }
+
+    pub fn edge_constraint(&self, slot: usize) -> Constraint {
+        self.edges[slot]
+    }
+
+    /// True when the centers of the cube are those of the target, as face turns keep them.
+    pub fn centers_match(&self, cube: &PackedCube) -> bool {
+        (0..6).all(|slot| cube.center(slot) == self.target.center(slot))
+    }
+
+    /// The corners and edges breaking their constraint.
+    pub fn mismatches(&self, cube: &PackedCube) -> (usize, usize) {
+        let corners = (0..8)
+            .filter(|slot| !self.corners[*slot].accepts(cube.corner(*slot), self.target.corner(*slot)))
+            .count();
+        let edges = (0..12)
+            .filter(|slot| !self.edges[*slot].accepts(cube.edge(*slot), self.target.edge(*slot)))
+            .count();
+        (corners, edges)
+    }
+
+    pub fn matches_packed(&self, cube: &PackedCube) -> bool {
+        self.centers_match(cube) && self.mismatches(cube) == (0, 0)
+    }
+
+    /// True when the model matches the mask, an invalid model never does.
+    pub fn matches(&self, model: &CubeModel) -> bool {
+        PackedCube::try_from(model).is_ok_and(|cube| self.matches_packed(&cube))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets() {
+        let mut model = CubeModel::solved();
+        assert!(MaskedModel::first_two_layers(Face::Down).matches(&model));
+        model.up_clockwise();
+        assert!(!MaskedModel::exact(&CubeModel::solved()).unwrap().matches(&model));
+        assert!(MaskedModel::first_two_layers(Face::Down).matches(&model));
+        assert!(MaskedModel::cross(Face::Down).matches(&model));
+        assert!(!MaskedModel::cross(Face::Up).matches(&model));
+        model.right_clockwise();
+        assert!(!MaskedModel::cross(Face::Down).matches(&model));
+        // face turns of U, D, R and L keep the edge orientation
+        assert!(MaskedModel::edge_orientation().matches(&model));
+        model.front_clockwise();
+        assert!(!MaskedModel::edge_orientation().matches(&model));
+        assert!(MaskedModel::solved().matches(&model));
+    }
+
+    #[test]
+    fn constraints() {
+        let mut model = CubeModel::solved();
+        model.right_half_turn();
+        let cube = PackedCube::try_from(&model).unwrap();
+        let mask = MaskedModel::solved().with_corners_on(Face::Right, Constraint::Orientation);
+        assert!(mask.matches_packed(&cube));
+        let mask = mask.with_edges_on(Face::Right, Constraint::Placed);
+        assert_eq!(mask.mismatches(&cube), (0, 4));
+        assert_eq!(MaskedModel::layer(Face::Right).mismatches(&cube), (4, 4));
+    }
+
+    #[test]
+    fn centers_must_match() {
+        let mut model = CubeModel::solved();
+        model.rotation_y_clockwise();
+        assert!(!MaskedModel::solved().matches(&model));
+        assert!(MaskedModel::new(&model).unwrap().matches(&model));
+    }
+}
@@ -1,10 +1,25 @@
 mod model;
 mod loaders;
+mod validation;
+mod packed;
+mod symmetry;
+mod mask;
 
-pub(crate) use model::CubeModel;
-pub(crate) use model::CubeElement;
-pub(crate) use model::TileColor;
-pub(crate) use model::SOLVED_INDEX_MAP;
+pub use model::CubeModel;
+pub use model::CubeElement;
+pub use model::TileColor;
+pub use model::Face;
+pub use model::{SOLVED_INDEX_MAP, CORNER_SLOTS, EDGE_SLOTS, CENTER_SLOTS};
+pub use model::slot_faces;
 
-pub(crate) use loaders::CubeTextLoader;
-pub(crate) use loaders::CubeLoader;
\ No newline at end of file
+pub use loaders::{CubeTextLoader, FaceletLoader};
+pub use loaders::CubeLoader;
+pub use loaders::{LoadError, TileLocation};
+
+pub use validation::ValidationError;
+
+pub use packed::PackedCube;
+
+pub use symmetry::Symmetry;
+
+pub use mask::{Constraint, MaskedModel};
@@ -1,5 +1,6 @@
 use std::fmt::Display;
-use super::loaders::CubeLoader;
+use std::ops::RangeInclusive;
+use super::loaders::{CubeLoader, LoadError};
 
 pub const SOLVED_INDEX_MAP: [((usize, usize, usize), CubeElement); 27] = [
     ((0,0,0), CubeElement::YellowRedBlue),
@@ -31,203 +32,476 @@ pub const SOLVED_INDEX_MAP: [((usize, usize, usize), CubeElement); 27] = [
     ((2,2,2), CubeElement::WhiteOrangeGreen)
 ];
 
+/// Corner slots in the order URF, UFL, ULB, UBR, DFR, DLF, DBL, DRB.
+/// The faces of each slot are listed clockwise, starting from the Up or Down face.
+pub const CORNER_SLOTS: [((usize, usize, usize), [Face; 3]); 8] = [
+    ((2,2,0), [Face::Up, Face::Right, Face::Front]),
+    ((0,2,0), [Face::Up, Face::Front, Face::Left]),
+    ((0,2,2), [Face::Up, Face::Left, Face::Back]),
+    ((2,2,2), [Face::Up, Face::Back, Face::Right]),
+    ((2,0,0), [Face::Down, Face::Front, Face::Right]),
+    ((0,0,0), [Face::Down, Face::Left, Face::Front]),
+    ((0,0,2), [Face::Down, Face::Back, Face::Left]),
+    ((2,0,2), [Face::Down, Face::Right, Face::Back]),
+];
+
+/// Edge slots in the order UR, UF, UL, UB, DR, DF, DL, DB, FR, FL, BL, BR.
+/// The first face of each slot is the Up or Down face, or the Front or Back face for the middle layer.
+pub const EDGE_SLOTS: [((usize, usize, usize), [Face; 2]); 12] = [
+    ((2,2,1), [Face::Up, Face::Right]),
+    ((1,2,0), [Face::Up, Face::Front]),
+    ((0,2,1), [Face::Up, Face::Left]),
+    ((1,2,2), [Face::Up, Face::Back]),
+    ((2,0,1), [Face::Down, Face::Right]),
+    ((1,0,0), [Face::Down, Face::Front]),
+    ((0,0,1), [Face::Down, Face::Left]),
+    ((1,0,2), [Face::Down, Face::Back]),
+    ((2,1,0), [Face::Front, Face::Right]),
+    ((0,1,0), [Face::Front, Face::Left]),
+    ((0,1,2), [Face::Back, Face::Left]),
+    ((2,1,2), [Face::Back, Face::Right]),
+];
+
+/// Center slots, one for each face.
+pub const CENTER_SLOTS: [((usize, usize, usize), [Face; 1]); 6] = [
+    ((1,2,1), [Face::Up]),
+    ((1,0,1), [Face::Down]),
+    ((1,1,0), [Face::Front]),
+    ((1,1,2), [Face::Back]),
+    ((2,1,1), [Face::Right]),
+    ((0,1,1), [Face::Left]),
+];
+
+/// Returns the faces touched by the slot at the given index, in the order used to measure orientation.
+pub fn slot_faces((x, y, z): (usize, usize, usize)) -> &'static [Face] {
+    if let Some((_, faces)) = CORNER_SLOTS.iter().find(|(pos, _)| *pos == (x, y, z)) {
+        faces
+    } else if let Some((_, faces)) = EDGE_SLOTS.iter().find(|(pos, _)| *pos == (x, y, z)) {
+        faces
+    } else if let Some((_, faces)) = CENTER_SLOTS.iter().find(|(pos, _)| *pos == (x, y, z)) {
+        faces
+    } else {
+        &[]
+    }
+}
+
+/// The six faces of the cube, seen from the outside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    Up,
+    Down,
+    Front,
+    Back,
+    Right,
+    Left,
+}
+
+impl Face {
+    pub fn opposite(self) -> Self {
+        match self {
+            Face::Up => Face::Down,
+            Face::Down => Face::Up,
+            Face::Front => Face::Back,
+            Face::Back => Face::Front,
+            Face::Right => Face::Left,
+            Face::Left => Face::Right,
+        }
+    }
+
+    /// Color of the face on the solved cube.
+    pub fn color(self) -> TileColor {
+        match self {
+            Face::Up => TileColor::White,
+            Face::Down => TileColor::Yellow,
+            Face::Front => TileColor::Blue,
+            Face::Back => TileColor::Green,
+            Face::Right => TileColor::Orange,
+            Face::Left => TileColor::Red,
+        }
+    }
+
+    pub fn normal(self) -> (isize, isize, isize) {
+        match self {
+            Face::Up => (0, 1, 0),
+            Face::Down => (0, -1, 0),
+            Face::Front => (0, 0, -1),
+            Face::Back => (0, 0, 1),
+            Face::Right => (1, 0, 0),
+            Face::Left => (-1, 0, 0),
+        }
+    }
+
+    pub(super) fn from_normal(normal: (isize, isize, isize)) -> Self {
+        match normal {
+            (0, 1, 0) => Face::Up,
+            (0, -1, 0) => Face::Down,
+            (0, 0, -1) => Face::Front,
+            (0, 0, 1) => Face::Back,
+            (1, 0, 0) => Face::Right,
+            (-1, 0, 0) => Face::Left,
+            _ => panic!("Invalid face normal"),
+        }
+    }
+
+    /// Rotates a vector centered in the kernel by a quarter turn, clockwise as seen from this face.
+    fn rotate_clockwise(self, (x, y, z): (isize, isize, isize)) -> (isize, isize, isize) {
+        match self {
+            Face::Right => (x, -z, y),
+            Face::Left => (x, z, -y),
+            Face::Up => (z, y, -x),
+            Face::Down => (-z, y, x),
+            Face::Front => (y, -x, z),
+            Face::Back => (-y, x, z),
+        }
+    }
+}
+
 /// CubeModel is thought to model the Rubik's cube with (0,0,0) being the yellow-red-blue vertix.
 /// The x-axis is from Red to Orange (left to right)
 /// The y-axis is from Yellow to White (bottom to top)
 /// The z-axis is from Blue to Green (front to back).
-#[derive(Debug, PartialEq, Eq, Clone)]
+///
+/// Every element also carries an orientation: the index, in `slot_faces`, of the face its
+/// first sticker looks at. Corners range in 0..3 and edges in 0..2, 0 meaning oriented.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
 pub struct CubeModel {
     cube_elements: [[[CubeElement; 3]; 3]; 3],
+    orientations: [[[u8; 3]; 3]; 3],
 }
 
 impl CubeModel {
-    pub fn from_loader(loader: impl CubeLoader) -> Self {
+    pub fn new(cube_elements: [[[CubeElement; 3]; 3]; 3], orientations: [[[u8; 3]; 3]; 3]) -> Self {
         Self {
-            cube_elements: loader.to_model_elements(),
+            cube_elements,
+            orientations,
         }
     }
 
+    pub fn from_loader(loader: impl CubeLoader) -> Result<Self, LoadError> {
+        loader.to_model()
+    }
+
     pub fn cube_elements(&self) -> &[[[CubeElement; 3]; 3]; 3] {
         &self.cube_elements
     }
 
-    pub fn right_clockwise(&mut self) {
-        let mut temp = self.cube_elements[2][0][0];
-        self.cube_elements[2][0][0] = self.cube_elements[2][0][2];
-        self.cube_elements[2][0][2] = self.cube_elements[2][2][2];
-        self.cube_elements[2][2][2] = self.cube_elements[2][2][0];
-        self.cube_elements[2][2][0] = temp;
+    pub fn orientations(&self) -> &[[[u8; 3]; 3]; 3] {
+        &self.orientations
+    }
 
-        temp = self.cube_elements[2][0][1];
-        self.cube_elements[2][0][1] = self.cube_elements[2][1][2];
-        self.cube_elements[2][1][2] = self.cube_elements[2][2][1];
-        self.cube_elements[2][2][1] = self.cube_elements[2][1][0];
-        self.cube_elements[2][1][0] = temp;
+    /// Color of the tile of the element at `position` that looks at `face`.
+    pub fn tile_color(&self, (x, y, z): (usize, usize, usize), face: Face) -> TileColor {
+        let faces = slot_faces((x, y, z));
+        let index = faces.iter().position(|f| *f == face).expect("The slot does not touch the face");
+        let colors = self.cube_elements[x][y][z].oriented_colors();
+        let orientation = self.orientations[x][y][z] as usize;
+        colors[(index + colors.len() - orientation) % colors.len()]
     }
 
-    pub fn right_counter_clockwise(&mut self) {
-        let mut temp = self.cube_elements[2][0][0];
-        self.cube_elements[2][0][0] = self.cube_elements[2][2][0];
-        self.cube_elements[2][2][0] = self.cube_elements[2][2][2];
-        self.cube_elements[2][2][2] = self.cube_elements[2][0][2];
-        self.cube_elements[2][0][2] = temp;
+    /// Turns by a quarter the layers at the given depths from `face`, where depth 0 is the face itself.
+    /// Elements move along with the layer and their orientation follows the face their first sticker ends up on.
+    fn turn(&mut self, face: Face, depths: RangeInclusive<usize>, clockwise: bool) {
+        let rotation = if clockwise { face } else { face.opposite() };
+        let normal = face.normal();
+        let old_elements = self.cube_elements;
+        let old_orientations = self.orientations;
+        for x in 0..3 {
+            for y in 0..3 {
+                for z in 0..3 {
+                    let centered = (x as isize - 1, y as isize - 1, z as isize - 1);
+                    let height = centered.0 * normal.0 + centered.1 * normal.1 + centered.2 * normal.2;
+                    if !depths.contains(&((1 - height) as usize)) {
+                        continue;
+                    }
+                    let (i, j, k) = rotation.rotate_clockwise(centered);
+                    let to = ((i + 1) as usize, (j + 1) as usize, (k + 1) as usize);
+                    self.cube_elements[to.0][to.1][to.2] = old_elements[x][y][z];
 
-        temp = self.cube_elements[2][0][1];
-        self.cube_elements[2][0][1] = self.cube_elements[2][1][0];
-        self.cube_elements[2][1][0] = self.cube_elements[2][2][1];
-        self.cube_elements[2][2][1] = self.cube_elements[2][1][2];
-        self.cube_elements[2][1][2] = temp;
+                    let from_faces = slot_faces((x, y, z));
+                    if from_faces.len() > 1 {
+                        let facing = from_faces[old_orientations[x][y][z] as usize];
+                        let facing = Face::from_normal(rotation.rotate_clockwise(facing.normal()));
+                        let orientation = slot_faces(to).iter().position(|f| *f == facing).unwrap();
+                        self.orientations[to.0][to.1][to.2] = orientation as u8;
+                    }
+                }
+            }
+        }
     }
 
-    pub fn left_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][0][0];
-        self.cube_elements[0][0][0] = self.cube_elements[0][2][0];
-        self.cube_elements[0][2][0] = self.cube_elements[0][2][2];
-        self.cube_elements[0][2][2] = self.cube_elements[0][0][2];
-        self.cube_elements[0][0][2] = temp;
+    pub fn right_clockwise(&mut self) {
+        self.turn(Face::Right, 0..=0, true);
+    }
 
-        temp = self.cube_elements[0][0][1];
-        self.cube_elements[0][0][1] = self.cube_elements[0][1][0];
-        self.cube_elements[0][1][0] = self.cube_elements[0][2][1];
-        self.cube_elements[0][2][1] = self.cube_elements[0][1][2];
-        self.cube_elements[0][1][2] = temp;
+    pub fn right_counter_clockwise(&mut self) {
+        self.turn(Face::Right, 0..=0, false);
     }
 
-    pub fn left_counter_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][0][0];
-        self.cube_elements[0][0][0] = self.cube_elements[0][0][2];
-        self.cube_elements[0][0][2] = self.cube_elements[0][2][2];
-        self.cube_elements[0][2][2] = self.cube_elements[0][2][0];
-        self.cube_elements[0][2][0] = temp;
+    pub fn left_clockwise(&mut self) {
+        self.turn(Face::Left, 0..=0, true);
+    }
 
-        temp = self.cube_elements[0][0][1];
-        self.cube_elements[0][0][1] = self.cube_elements[0][1][2];
-        self.cube_elements[0][1][2] = self.cube_elements[0][2][1];
-        self.cube_elements[0][2][1] = self.cube_elements[0][1][0];
-        self.cube_elements[0][1][0] = temp;
+    pub fn left_counter_clockwise(&mut self) {
+        self.turn(Face::Left, 0..=0, false);
     }
 
     pub fn up_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][2][0];
-        self.cube_elements[0][2][0] = self.cube_elements[2][2][0];
-        self.cube_elements[2][2][0] = self.cube_elements[2][2][2];
-        self.cube_elements[2][2][2] = self.cube_elements[0][2][2];
-        self.cube_elements[0][2][2] = temp;
-
-        temp = self.cube_elements[0][2][1];
-        self.cube_elements[0][2][1] = self.cube_elements[1][2][0];
-        self.cube_elements[1][2][0] = self.cube_elements[2][2][1];
-        self.cube_elements[2][2][1] = self.cube_elements[1][2][2];
-        self.cube_elements[1][2][2] = temp;
+        self.turn(Face::Up, 0..=0, true);
     }
 
     pub fn up_counter_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][2][0];
-        self.cube_elements[0][2][0] = self.cube_elements[0][2][2];
-        self.cube_elements[0][2][2] = self.cube_elements[2][2][2];
-        self.cube_elements[2][2][2] = self.cube_elements[2][2][0];
-        self.cube_elements[2][2][0] = temp;
-
-        temp = self.cube_elements[0][2][1];
-        self.cube_elements[0][2][1] = self.cube_elements[1][2][2];
-        self.cube_elements[1][2][2] = self.cube_elements[2][2][1];
-        self.cube_elements[2][2][1] = self.cube_elements[1][2][0];
-        self.cube_elements[1][2][0] = temp;
+        self.turn(Face::Up, 0..=0, false);
     }
 
     pub fn down_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][0][0];
-        self.cube_elements[0][0][0] = self.cube_elements[0][0][2];
-        self.cube_elements[0][0][2] = self.cube_elements[2][0][2];
-        self.cube_elements[2][0][2] = self.cube_elements[2][0][0];
-        self.cube_elements[2][0][0] = temp;
-
-        temp = self.cube_elements[0][0][1];
-        self.cube_elements[0][0][1] = self.cube_elements[1][0][2];
-        self.cube_elements[1][0][2] = self.cube_elements[2][0][1];
-        self.cube_elements[2][0][1] = self.cube_elements[1][0][0];
-        self.cube_elements[1][0][0] = temp;
+        self.turn(Face::Down, 0..=0, true);
     }
 
     pub fn down_counter_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][0][0];
-        self.cube_elements[0][0][0] = self.cube_elements[2][0][0];
-        self.cube_elements[2][0][0] = self.cube_elements[2][0][2];
-        self.cube_elements[2][0][2] = self.cube_elements[0][0][2];
-        self.cube_elements[0][0][2] = temp;
-
-        temp = self.cube_elements[0][0][1];
-        self.cube_elements[0][0][1] = self.cube_elements[1][0][0];
-        self.cube_elements[1][0][0] = self.cube_elements[2][0][1];
-        self.cube_elements[2][0][1] = self.cube_elements[1][0][2];
-        self.cube_elements[1][0][2] = temp;
+        self.turn(Face::Down, 0..=0, false);
     }
 
     pub fn front_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][0][0];
-        self.cube_elements[0][0][0] = self.cube_elements[2][0][0];
-        self.cube_elements[2][0][0] = self.cube_elements[2][2][0];
-        self.cube_elements[2][2][0] = self.cube_elements[0][2][0];
-        self.cube_elements[0][2][0] = temp;
-
-        temp = self.cube_elements[0][1][0];
-        self.cube_elements[0][1][0] = self.cube_elements[1][0][0];
-        self.cube_elements[1][0][0] = self.cube_elements[2][1][0];
-        self.cube_elements[2][1][0] = self.cube_elements[1][2][0];
-        self.cube_elements[1][2][0] = temp;
+        self.turn(Face::Front, 0..=0, true);
     }
 
     pub fn front_counter_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][0][0];
-        self.cube_elements[0][0][0] = self.cube_elements[0][2][0];
-        self.cube_elements[0][2][0] = self.cube_elements[2][2][0];
-        self.cube_elements[2][2][0] = self.cube_elements[2][0][0];
-        self.cube_elements[2][0][0] = temp;
-
-        temp = self.cube_elements[0][1][0];
-        self.cube_elements[0][1][0] = self.cube_elements[1][2][0];
-        self.cube_elements[1][2][0] = self.cube_elements[2][1][0];
-        self.cube_elements[2][1][0] = self.cube_elements[1][0][0];
-        self.cube_elements[1][0][0] = temp;
+        self.turn(Face::Front, 0..=0, false);
     }
 
     pub fn back_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][0][2];
-        self.cube_elements[0][0][2] = self.cube_elements[0][2][2];
-        self.cube_elements[0][2][2] = self.cube_elements[2][2][2];
-        self.cube_elements[2][2][2] = self.cube_elements[2][0][2];
-        self.cube_elements[2][0][2] = temp;
-
-        temp = self.cube_elements[0][1][2];
-        self.cube_elements[0][1][2] = self.cube_elements[1][2][2];
-        self.cube_elements[1][2][2] = self.cube_elements[2][1][2];
-        self.cube_elements[2][1][2] = self.cube_elements[1][0][2];
-        self.cube_elements[1][0][2] = temp;
+        self.turn(Face::Back, 0..=0, true);
     }
 
     pub fn back_counter_clockwise(&mut self) {
-        let mut temp = self.cube_elements[0][0][2];
-        self.cube_elements[0][0][2] = self.cube_elements[2][0][2];
-        self.cube_elements[2][0][2] = self.cube_elements[2][2][2];
-        self.cube_elements[2][2][2] = self.cube_elements[0][2][2];
-        self.cube_elements[0][2][2] = temp;
+        self.turn(Face::Back, 0..=0, false);
+    }
+
+    pub fn right_half_turn(&mut self) {
+        self.turn(Face::Right, 0..=0, true);
+        self.turn(Face::Right, 0..=0, true);
+    }
+
+    pub fn left_half_turn(&mut self) {
+        self.turn(Face::Left, 0..=0, true);
+        self.turn(Face::Left, 0..=0, true);
+    }
+
+    pub fn up_half_turn(&mut self) {
+        self.turn(Face::Up, 0..=0, true);
+        self.turn(Face::Up, 0..=0, true);
+    }
+
+    pub fn down_half_turn(&mut self) {
+        self.turn(Face::Down, 0..=0, true);
+        self.turn(Face::Down, 0..=0, true);
+    }
+
+    pub fn front_half_turn(&mut self) {
+        self.turn(Face::Front, 0..=0, true);
+        self.turn(Face::Front, 0..=0, true);
+    }
+
+    pub fn back_half_turn(&mut self) {
+        self.turn(Face::Back, 0..=0, true);
+        self.turn(Face::Back, 0..=0, true);
+    }
+
+    // Slices turn the middle layer along with the face they are named after:
+    // middle follows left, equator follows down and standing follows front.
+    pub fn middle_clockwise(&mut self) {
+        self.turn(Face::Left, 1..=1, true);
+    }
+
+    pub fn middle_counter_clockwise(&mut self) {
+        self.turn(Face::Left, 1..=1, false);
+    }
+
+    pub fn middle_half_turn(&mut self) {
+        self.turn(Face::Left, 1..=1, true);
+        self.turn(Face::Left, 1..=1, true);
+    }
+
+    pub fn equator_clockwise(&mut self) {
+        self.turn(Face::Down, 1..=1, true);
+    }
+
+    pub fn equator_counter_clockwise(&mut self) {
+        self.turn(Face::Down, 1..=1, false);
+    }
+
+    pub fn equator_half_turn(&mut self) {
+        self.turn(Face::Down, 1..=1, true);
+        self.turn(Face::Down, 1..=1, true);
+    }
+
+    pub fn standing_clockwise(&mut self) {
+        self.turn(Face::Front, 1..=1, true);
+    }
+
+    pub fn standing_counter_clockwise(&mut self) {
+        self.turn(Face::Front, 1..=1, false);
+    }
+
+    pub fn standing_half_turn(&mut self) {
+        self.turn(Face::Front, 1..=1, true);
+        self.turn(Face::Front, 1..=1, true);
+    }
+
+    // Wide turns move the face along with the middle layer next to it.
+    pub fn right_wide_clockwise(&mut self) {
+        self.turn(Face::Right, 0..=1, true);
+    }
+
+    pub fn right_wide_counter_clockwise(&mut self) {
+        self.turn(Face::Right, 0..=1, false);
+    }
+
+    pub fn right_wide_half_turn(&mut self) {
+        self.turn(Face::Right, 0..=1, true);
+        self.turn(Face::Right, 0..=1, true);
+    }
+
+    pub fn left_wide_clockwise(&mut self) {
+        self.turn(Face::Left, 0..=1, true);
+    }
 
-        temp = self.cube_elements[0][1][2];
-        self.cube_elements[0][1][2] = self.cube_elements[1][0][2];
-        self.cube_elements[1][0][2] = self.cube_elements[2][1][2];
-        self.cube_elements[2][1][2] = self.cube_elements[1][2][2];
-        self.cube_elements[1][2][2] = temp;
+    pub fn left_wide_counter_clockwise(&mut self) {
+        self.turn(Face::Left, 0..=1, false);
+    }
+
+    pub fn left_wide_half_turn(&mut self) {
+        self.turn(Face::Left, 0..=1, true);
+        self.turn(Face::Left, 0..=1, true);
+    }
+
+    pub fn up_wide_clockwise(&mut self) {
+        self.turn(Face::Up, 0..=1, true);
+    }
+
+    pub fn up_wide_counter_clockwise(&mut self) {
+        self.turn(Face::Up, 0..=1, false);
+    }
+
+    pub fn up_wide_half_turn(&mut self) {
+        self.turn(Face::Up, 0..=1, true);
+        self.turn(Face::Up, 0..=1, true);
+    }
+
+    pub fn down_wide_clockwise(&mut self) {
+        self.turn(Face::Down, 0..=1, true);
+    }
+
+    pub fn down_wide_counter_clockwise(&mut self) {
+        self.turn(Face::Down, 0..=1, false);
+    }
+
+    pub fn down_wide_half_turn(&mut self) {
+        self.turn(Face::Down, 0..=1, true);
+        self.turn(Face::Down, 0..=1, true);
+    }
+
+    pub fn front_wide_clockwise(&mut self) {
+        self.turn(Face::Front, 0..=1, true);
+    }
+
+    pub fn front_wide_counter_clockwise(&mut self) {
+        self.turn(Face::Front, 0..=1, false);
+    }
+
+    pub fn front_wide_half_turn(&mut self) {
+        self.turn(Face::Front, 0..=1, true);
+        self.turn(Face::Front, 0..=1, true);
+    }
+
+    pub fn back_wide_clockwise(&mut self) {
+        self.turn(Face::Back, 0..=1, true);
+    }
+
+    pub fn back_wide_counter_clockwise(&mut self) {
+        self.turn(Face::Back, 0..=1, false);
+    }
+
+    pub fn back_wide_half_turn(&mut self) {
+        self.turn(Face::Back, 0..=1, true);
+        self.turn(Face::Back, 0..=1, true);
+    }
+
+    // Rotations turn the whole cube as x, y and z turn the right, up and front faces.
+    pub fn rotation_x_clockwise(&mut self) {
+        self.turn(Face::Right, 0..=2, true);
+    }
+
+    pub fn rotation_x_counter_clockwise(&mut self) {
+        self.turn(Face::Right, 0..=2, false);
+    }
+
+    pub fn rotation_x_half_turn(&mut self) {
+        self.turn(Face::Right, 0..=2, true);
+        self.turn(Face::Right, 0..=2, true);
+    }
+
+    pub fn rotation_y_clockwise(&mut self) {
+        self.turn(Face::Up, 0..=2, true);
+    }
+
+    pub fn rotation_y_counter_clockwise(&mut self) {
+        self.turn(Face::Up, 0..=2, false);
+    }
+
+    pub fn rotation_y_half_turn(&mut self) {
+        self.turn(Face::Up, 0..=2, true);
+        self.turn(Face::Up, 0..=2, true);
+    }
+
+    pub fn rotation_z_clockwise(&mut self) {
+        self.turn(Face::Front, 0..=2, true);
+    }
+
+    pub fn rotation_z_counter_clockwise(&mut self) {
+        self.turn(Face::Front, 0..=2, false);
+    }
+
+    pub fn rotation_z_half_turn(&mut self) {
+        self.turn(Face::Front, 0..=2, true);
+        self.turn(Face::Front, 0..=2, true);
     }
 
     pub fn solved() -> Self {
         let mut cube_elements = [[[CubeElement::White; 3]; 3]; 3];
-        SOLVED_INDEX_MAP.map(|((x, y, z), el)| cube_elements[x][y][z] = el);
-        Self { cube_elements }
+        for ((x, y, z), el) in SOLVED_INDEX_MAP {
+            cube_elements[x][y][z] = el;
+        }
+        Self {
+            cube_elements,
+            orientations: [[[0; 3]; 3]; 3],
+        }
     }
 
     pub fn is_solved(&self) -> bool {
-        let solved = Self::solved();
-        self.cube_elements == solved.cube_elements
+        *self == Self::solved()
+    }
+
+    /// Number of bad edges relative to the centers, the edges only F or B quarter turns can
+    /// orient, as counted by ZZ solvers and fewest moves solvers. An edge is good when the tile
+    /// of the color of the U or D center looks at the U or D face, or for the middle layer at
+    /// the F or B face, and when it has neither color, when the tile of the color of the F or B
+    /// center does. A cube that can be solved has an even number of them.
+    pub fn bad_edges(&self) -> usize {
+        let center_color = |face: Face| {
+            let (position, _) = CENTER_SLOTS.iter().find(|(_, faces)| faces[0] == face).unwrap();
+            self.tile_color(*position, face)
+        };
+        let up_down = [center_color(Face::Up), center_color(Face::Down)];
+        let front_back = [center_color(Face::Front), center_color(Face::Back)];
+        EDGE_SLOTS
+            .iter()
+            .filter(|(position, [first, second])| {
+                let (first, second) = (self.tile_color(*position, *first), self.tile_color(*position, *second));
+                let good = up_down.contains(&first) || (!up_down.contains(&second) && front_back.contains(&first));
+                !good
+            })
+            .count()
     }
 }
 
@@ -243,15 +517,15 @@ impl Display for CubeModel {
                     );
                     result.push_str(&s);
                 }
-                result.push_str("\n");
+                result.push('\n');
             }
         }
-        result.push_str("\n");
+        result.push('\n');
         f.write_str(&result)
     }
 }
 
-#[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
 pub enum TileColor {
     White,
     Orange,
@@ -261,7 +535,7 @@ pub enum TileColor {
     Yellow,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum CubeElement {
     WhiteOrangeGreen,
     WhiteBlueOrange,
@@ -293,7 +567,18 @@ pub enum CubeElement {
 }
 
 impl CubeElement {
-    pub fn to_tile_colors(&self) -> Vec<TileColor> {
+    /// Position of the element in the solved cube.
+    pub fn home(self) -> (usize, usize, usize) {
+        SOLVED_INDEX_MAP.iter().find(|(_, e)| *e == self).unwrap().0
+    }
+
+    /// Colors of the element in the same order as the faces of its home slot, see `slot_faces`.
+    /// The first color is the one used to measure the orientation.
+    pub fn oriented_colors(self) -> Vec<TileColor> {
+        slot_faces(self.home()).iter().map(|face| face.color()).collect()
+    }
+
+    pub fn to_tile_colors(self) -> Vec<TileColor> {
         match self {
             CubeElement::WhiteOrangeGreen => vec![
                 TileColor::White,
@@ -396,8 +681,6 @@ impl Display for CubeElement {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::loaders::CubeTextLoader;
-    use std::path::PathBuf;
 
     fn load_solved_cube() -> CubeModel {
         CubeModel::solved()
@@ -419,6 +702,14 @@ mod tests {
             assert_eq!(cube.cube_elements[2][2][0], CubeElement::YellowBlueOrange);
             assert_eq!(cube.cube_elements[2][2][1], CubeElement::OrangeBlue);
             assert_eq!(cube.cube_elements[2][2][2], CubeElement::WhiteBlueOrange);
+            assert_eq!(cube.orientations[2][2][0], 2);
+            assert_eq!(cube.orientations[2][2][2], 1);
+            assert_eq!(cube.orientations[2][0][0], 1);
+            assert_eq!(cube.orientations[2][0][2], 2);
+            assert_eq!(cube.orientations[2][0][1], 0);
+            assert_eq!(cube.orientations[2][1][0], 0);
+            assert_eq!(cube.orientations[2][1][2], 0);
+            assert_eq!(cube.orientations[2][2][1], 0);
         }
 
         #[test]
@@ -434,6 +725,14 @@ mod tests {
             assert_eq!(cube.cube_elements[2][2][0], CubeElement::WhiteOrangeGreen);
             assert_eq!(cube.cube_elements[2][2][1], CubeElement::OrangeGreen);
             assert_eq!(cube.cube_elements[2][2][2], CubeElement::YellowOrangeGreen);
+            assert_eq!(cube.orientations[2][2][0], 2);
+            assert_eq!(cube.orientations[2][2][2], 1);
+            assert_eq!(cube.orientations[2][0][0], 1);
+            assert_eq!(cube.orientations[2][0][2], 2);
+            assert_eq!(cube.orientations[2][0][1], 0);
+            assert_eq!(cube.orientations[2][1][0], 0);
+            assert_eq!(cube.orientations[2][1][2], 0);
+            assert_eq!(cube.orientations[2][2][1], 0);
         }
 
         #[test]
@@ -449,6 +748,14 @@ mod tests {
             assert_eq!(cube.cube_elements[0][2][0], CubeElement::WhiteGreenRed);
             assert_eq!(cube.cube_elements[0][2][1], CubeElement::RedGreen);
             assert_eq!(cube.cube_elements[0][2][2], CubeElement::YellowGreenRed);
+            assert_eq!(cube.orientations[0][2][0], 1);
+            assert_eq!(cube.orientations[0][2][2], 2);
+            assert_eq!(cube.orientations[0][0][0], 2);
+            assert_eq!(cube.orientations[0][0][2], 1);
+            assert_eq!(cube.orientations[0][0][1], 0);
+            assert_eq!(cube.orientations[0][1][0], 0);
+            assert_eq!(cube.orientations[0][1][2], 0);
+            assert_eq!(cube.orientations[0][2][1], 0);
         }
 
         #[test]
@@ -464,6 +771,14 @@ mod tests {
             assert_eq!(cube.cube_elements[0][2][0], CubeElement::YellowRedBlue);
             assert_eq!(cube.cube_elements[0][2][1], CubeElement::RedBlue);
             assert_eq!(cube.cube_elements[0][2][2], CubeElement::WhiteRedBlue);
+            assert_eq!(cube.orientations[0][2][0], 1);
+            assert_eq!(cube.orientations[0][2][2], 2);
+            assert_eq!(cube.orientations[0][0][0], 2);
+            assert_eq!(cube.orientations[0][0][2], 1);
+            assert_eq!(cube.orientations[0][0][1], 0);
+            assert_eq!(cube.orientations[0][1][0], 0);
+            assert_eq!(cube.orientations[0][1][2], 0);
+            assert_eq!(cube.orientations[0][2][1], 0);
         }
 
         #[test]
@@ -479,6 +794,7 @@ mod tests {
             assert_eq!(cube.cube_elements[2][2][0], CubeElement::WhiteOrangeGreen);
             assert_eq!(cube.cube_elements[2][2][1], CubeElement::WhiteGreen);
             assert_eq!(cube.cube_elements[2][2][2], CubeElement::WhiteGreenRed);
+            assert_eq!(cube.orientations, [[[0; 3]; 3]; 3]);
         }
 
         #[test]
@@ -494,6 +810,7 @@ mod tests {
             assert_eq!(cube.cube_elements[2][2][0], CubeElement::WhiteRedBlue);
             assert_eq!(cube.cube_elements[2][2][1], CubeElement::WhiteBlue);
             assert_eq!(cube.cube_elements[2][2][2], CubeElement::WhiteBlueOrange);
+            assert_eq!(cube.orientations, [[[0; 3]; 3]; 3]);
         }
 
         #[test]
@@ -509,6 +826,7 @@ mod tests {
             assert_eq!(cube.cube_elements[2][0][0], CubeElement::YellowRedBlue);
             assert_eq!(cube.cube_elements[2][0][1], CubeElement::YellowBlue);
             assert_eq!(cube.cube_elements[2][0][2], CubeElement::YellowBlueOrange);
+            assert_eq!(cube.orientations, [[[0; 3]; 3]; 3]);
         }
 
         #[test]
@@ -524,6 +842,7 @@ mod tests {
             assert_eq!(cube.cube_elements[2][0][0], CubeElement::YellowOrangeGreen);
             assert_eq!(cube.cube_elements[2][0][1], CubeElement::YellowGreen);
             assert_eq!(cube.cube_elements[2][0][2], CubeElement::YellowGreenRed);
+            assert_eq!(cube.orientations, [[[0; 3]; 3]; 3]);
         }
 
         #[test]
@@ -539,6 +858,14 @@ mod tests {
             assert_eq!(cube.cube_elements[2][0][0], CubeElement::WhiteBlueOrange);
             assert_eq!(cube.cube_elements[2][1][0], CubeElement::WhiteBlue);
             assert_eq!(cube.cube_elements[2][2][0], CubeElement::WhiteRedBlue);
+            assert_eq!(cube.orientations[2][2][0], 1);
+            assert_eq!(cube.orientations[0][2][0], 2);
+            assert_eq!(cube.orientations[2][0][0], 2);
+            assert_eq!(cube.orientations[0][0][0], 1);
+            assert_eq!(cube.orientations[1][2][0], 1);
+            assert_eq!(cube.orientations[1][0][0], 1);
+            assert_eq!(cube.orientations[2][1][0], 1);
+            assert_eq!(cube.orientations[0][1][0], 1);
         }
 
         #[test]
@@ -554,6 +881,14 @@ mod tests {
             assert_eq!(cube.cube_elements[2][0][0], CubeElement::YellowRedBlue);
             assert_eq!(cube.cube_elements[2][1][0], CubeElement::YellowBlue);
             assert_eq!(cube.cube_elements[2][2][0], CubeElement::YellowBlueOrange);
+            assert_eq!(cube.orientations[2][2][0], 1);
+            assert_eq!(cube.orientations[0][2][0], 2);
+            assert_eq!(cube.orientations[2][0][0], 2);
+            assert_eq!(cube.orientations[0][0][0], 1);
+            assert_eq!(cube.orientations[1][2][0], 1);
+            assert_eq!(cube.orientations[1][0][0], 1);
+            assert_eq!(cube.orientations[2][1][0], 1);
+            assert_eq!(cube.orientations[0][1][0], 1);
         }
 
         #[test]
@@ -569,6 +904,14 @@ mod tests {
             assert_eq!(cube.cube_elements[2][0][2], CubeElement::YellowGreenRed);
             assert_eq!(cube.cube_elements[2][1][2], CubeElement::YellowGreen);
             assert_eq!(cube.cube_elements[2][2][2], CubeElement::YellowOrangeGreen);
+            assert_eq!(cube.orientations[0][2][2], 1);
+            assert_eq!(cube.orientations[2][2][2], 2);
+            assert_eq!(cube.orientations[0][0][2], 2);
+            assert_eq!(cube.orientations[2][0][2], 1);
+            assert_eq!(cube.orientations[1][2][2], 1);
+            assert_eq!(cube.orientations[1][0][2], 1);
+            assert_eq!(cube.orientations[0][1][2], 1);
+            assert_eq!(cube.orientations[2][1][2], 1);
         }
 
         #[test]
@@ -584,8 +927,131 @@ mod tests {
             assert_eq!(cube.cube_elements[2][0][2], CubeElement::WhiteOrangeGreen);
             assert_eq!(cube.cube_elements[2][1][2], CubeElement::WhiteGreen);
             assert_eq!(cube.cube_elements[2][2][2], CubeElement::WhiteGreenRed);
+            assert_eq!(cube.orientations[0][2][2], 1);
+            assert_eq!(cube.orientations[2][2][2], 2);
+            assert_eq!(cube.orientations[0][0][2], 2);
+            assert_eq!(cube.orientations[2][0][2], 1);
+            assert_eq!(cube.orientations[1][2][2], 1);
+            assert_eq!(cube.orientations[1][0][2], 1);
+            assert_eq!(cube.orientations[0][1][2], 1);
+            assert_eq!(cube.orientations[2][1][2], 1);
         }
         
+        #[test]
+        fn sexy_move_has_order_six() {
+            let mut cube = load_solved_cube();
+            for i in 0..6 {
+                cube.right_clockwise();
+                cube.up_clockwise();
+                cube.right_counter_clockwise();
+                cube.up_counter_clockwise();
+                assert_eq!(cube.is_solved(), i == 5);
+            }
+        }
+
+        #[test]
+        fn twisted_corner_is_not_solved() {
+            let mut cube = load_solved_cube();
+            cube.orientations[2][2][0] = 1;
+            assert_ne!(cube, load_solved_cube());
+            assert!(!cube.is_solved());
+        }
+
+        #[test]
+        fn flipped_edge_is_not_solved() {
+            let mut cube = load_solved_cube();
+            cube.orientations[1][2][0] = 1;
+            assert_ne!(cube, load_solved_cube());
+            assert!(!cube.is_solved());
+        }
+
+        #[test]
+        fn half_turns_are_two_quarter_turns() {
+            let mut half = load_solved_cube();
+            let mut quarters = load_solved_cube();
+            half.front_half_turn();
+            half.right_half_turn();
+            quarters.front_clockwise();
+            quarters.front_clockwise();
+            quarters.right_counter_clockwise();
+            quarters.right_counter_clockwise();
+            assert_eq!(half, quarters);
+        }
+
+        #[test]
+        fn rotations_are_face_and_slice_turns() {
+            let mut rotated = load_solved_cube();
+            let mut turned = load_solved_cube();
+            rotated.rotation_x_clockwise();
+            turned.right_clockwise();
+            turned.middle_counter_clockwise();
+            turned.left_counter_clockwise();
+            assert_eq!(rotated, turned);
+
+            rotated.rotation_y_clockwise();
+            turned.up_clockwise();
+            turned.equator_counter_clockwise();
+            turned.down_counter_clockwise();
+            assert_eq!(rotated, turned);
+
+            rotated.rotation_z_clockwise();
+            turned.front_clockwise();
+            turned.standing_clockwise();
+            turned.back_counter_clockwise();
+            assert_eq!(rotated, turned);
+        }
+
+        #[test]
+        fn wide_turns_are_face_and_slice_turns() {
+            let mut wide = load_solved_cube();
+            let mut turned = load_solved_cube();
+            wide.right_wide_clockwise();
+            turned.right_clockwise();
+            turned.middle_counter_clockwise();
+            assert_eq!(wide, turned);
+
+            wide.down_wide_clockwise();
+            turned.down_clockwise();
+            turned.equator_clockwise();
+            assert_eq!(wide, turned);
+
+            wide.back_wide_counter_clockwise();
+            turned.back_counter_clockwise();
+            turned.standing_clockwise();
+            assert_eq!(wide, turned);
+        }
+
+        #[test]
+        fn middle_moves_centers_and_flips_edges() {
+            let mut cube = load_solved_cube();
+            cube.middle_clockwise();
+            // the middle slice follows the left face, so the up center goes to the front
+            assert_eq!(cube.cube_elements[1][1][0], CubeElement::White);
+            assert_eq!(cube.cube_elements[1][0][1], CubeElement::Blue);
+            assert_eq!(cube.cube_elements[1][2][0], CubeElement::WhiteGreen);
+            assert_eq!(cube.orientations[1][2][0], 1);
+            assert_eq!(cube.orientations[1][0][0], 1);
+            assert_eq!(cube.orientations[1][0][2], 1);
+            assert_eq!(cube.orientations[1][2][2], 1);
+        }
+
+        #[test]
+        fn bad_edges() {
+            let mut cube = load_solved_cube();
+            cube.right_clockwise();
+            cube.up_clockwise();
+            assert_eq!(cube.bad_edges(), 0);
+            cube.front_clockwise();
+            assert_eq!(cube.bad_edges(), 4);
+            cube.back_counter_clockwise();
+            assert_eq!(cube.bad_edges(), 8);
+            cube.front_counter_clockwise();
+            assert_eq!(cube.bad_edges(), 4);
+            // relative to the centers, upside down the front stays in front
+            cube.rotation_z_half_turn();
+            assert_eq!(cube.bad_edges(), 4);
+        }
+
         #[test]
         fn print() {
             let cube = load_solved_cube();
@@ -0,0 +1,363 @@
+use super::model::{CubeElement, CubeModel, CENTER_SLOTS, CORNER_SLOTS, EDGE_SLOTS, SOLVED_INDEX_MAP};
+use super::validation::ValidationError;
+
+/// Bits used by each corner: its index in `CORNER_SLOTS` on 3 bits then its orientation on 2.
+const CORNER_BITS: usize = 5;
+/// Bits used by each edge: its index in `EDGE_SLOTS` on 4 bits then its orientation on 1.
+const EDGE_BITS: usize = 5;
+/// Bits used by each center: its index in `CENTER_SLOTS`.
+const CENTER_BITS: usize = 3;
+
+/// A cube packed in a few integers, cheap to copy, compare and hash, for the searches.
+/// For every slot, in the order of `CORNER_SLOTS`, `EDGE_SLOTS` and `CENTER_SLOTS`, it stores
+/// which piece the slot holds, by the index of the piece's home slot, and its orientation.
+/// A move is applied by multiplying with the packed cube of that move, see `multiply`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackedCube {
+    corners: u64,
+    edges: u64,
+    centers: u32,
+}
+
+fn element_at(pos: (usize, usize, usize)) -> CubeElement {
+    SOLVED_INDEX_MAP.iter().find(|(p, _)| *p == pos).unwrap().1
+}
+
+/// Rank of the permutation of distinct values in the factorial number system.
+fn permutation_rank(values: &[u8]) -> usize {
+    values.iter().enumerate().fold(0, |rank, (i, value)| {
+        let smaller = values[i + 1..].iter().filter(|v| *v < value).count();
+        rank * (values.len() - i) + smaller
+    })
+}
+
+impl PackedCube {
+    pub const SOLVED: PackedCube = PackedCube {
+        corners: Self::solved_pieces(8, CORNER_BITS),
+        edges: Self::solved_pieces(12, EDGE_BITS),
+        centers: Self::solved_pieces(6, CENTER_BITS) as u32,
+    };
+
+    const fn solved_pieces(count: usize, bits: usize) -> u64 {
+        let mut packed = 0;
+        let mut slot = 0;
+        while slot < count {
+            packed |= (slot as u64) << (slot * bits);
+            slot += 1;
+        }
+        packed
+    }
+
+    /// The piece in a corner slot and its orientation.
+    pub fn corner(&self, slot: usize) -> (u8, u8) {
+        let value = (self.corners >> (slot * CORNER_BITS)) as u8;
+        (value & 0b111, (value >> 3) & 0b11)
+    }
+
+    /// The piece in an edge slot and its orientation.
+    pub fn edge(&self, slot: usize) -> (u8, u8) {
+        let value = (self.edges >> (slot * EDGE_BITS)) as u8;
+        (value & 0b1111, (value >> 4) & 0b1)
+    }
+
+    /// The piece in every corner slot.
+    pub fn corner_pieces(&self) -> [u8; 8] {
+        std::array::from_fn(|slot| self.corner(slot).0)
+    }
+
+    /// The piece in every edge slot.
+    pub fn edge_pieces(&self) -> [u8; 12] {
+        std::array::from_fn(|slot| self.edge(slot).0)
+    }
+
+    /// The piece in a center slot.
+    pub fn center(&self, slot: usize) -> u8 {
+        (self.centers >> (slot * CENTER_BITS)) as u8 & 0b111
+    }
+
+    /// The cube obtained by applying `moves` to this one, `moves` being the packed cube of a
+    /// move or sequence of moves applied to the solved cube: each of its slots tells from which
+    /// slot the piece comes and the orientation it gains on the way.
+    pub fn multiply(&self, moves: &PackedCube) -> PackedCube {
+        let mut corners = 0;
+        for slot in 0..8 {
+            let (from, twist) = moves.corner(slot);
+            let (piece, orientation) = self.corner(from as usize);
+            let value = piece as u64 | (((orientation + twist) % 3) as u64) << 3;
+            corners |= value << (slot * CORNER_BITS);
+        }
+        let mut edges = 0;
+        for slot in 0..12 {
+            let (from, flip) = moves.edge(slot);
+            let (piece, orientation) = self.edge(from as usize);
+            let value = piece as u64 | (((orientation + flip) % 2) as u64) << 4;
+            edges |= value << (slot * EDGE_BITS);
+        }
+        let mut centers = 0;
+        for slot in 0..6 {
+            centers |= (self.center(moves.center(slot) as usize) as u32) << (slot * CENTER_BITS);
+        }
+        PackedCube {
+            corners,
+            edges,
+            centers,
+        }
+    }
+
+    /// The cube undoing this one: multiplying by it gives the solved cube.
+    /// Multiplying a cube by the inverse of a goal relabels it so that the goal becomes the
+    /// solved cube, the moves solving the relabeled cube lead from the cube to the goal.
+    pub fn inverse(&self) -> PackedCube {
+        let mut inverse = PackedCube {
+            corners: 0,
+            edges: 0,
+            centers: 0,
+        };
+        for slot in 0..8 {
+            let (piece, orientation) = self.corner(slot);
+            let value = slot as u64 | (((3 - orientation) % 3) as u64) << 3;
+            inverse.corners |= value << (piece as usize * CORNER_BITS);
+        }
+        for slot in 0..12 {
+            let (piece, orientation) = self.edge(slot);
+            let value = slot as u64 | (orientation as u64) << 4;
+            inverse.edges |= value << (piece as usize * EDGE_BITS);
+        }
+        for slot in 0..6 {
+            inverse.centers |= (slot as u32) << (self.center(slot) as usize * CENTER_BITS);
+        }
+        inverse
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == Self::SOLVED
+    }
+
+    /// True when every center is at home, as face turns keep it.
+    pub fn centers_solved(&self) -> bool {
+        self.centers == Self::SOLVED.centers
+    }
+
+    /// Rank of the permutation of the corners, in 0..8!.
+    pub fn corner_permutation(&self) -> u16 {
+        permutation_rank(&self.corner_pieces()) as u16
+    }
+
+    /// Orientations of the first 7 corners in base 3, in 0..3^7, the last one is implied.
+    pub fn corner_orientation(&self) -> u16 {
+        (0..7).fold(0, |coordinate, slot| coordinate * 3 + self.corner(slot).1 as u16)
+    }
+
+    /// Rank of the permutation of the edges, in 0..12!.
+    pub fn edge_permutation(&self) -> u32 {
+        permutation_rank(&self.edge_pieces()) as u32
+    }
+
+    /// Orientations of the first 11 edges in base 2, in 0..2^11, the last one is implied.
+    pub fn edge_orientation(&self) -> u16 {
+        (0..11).fold(0, |coordinate, slot| coordinate * 2 + self.edge(slot).1 as u16)
+    }
+}
+
+/// Packs a model, failing if an element is not in a slot of its kind, is found more than once,
+/// or has an orientation out of the range of its kind, so that it never spills into the bits of
+/// the next slot. The model need not be solvable, see `CubeModel::validate`.
+impl TryFrom<&CubeModel> for PackedCube {
+    type Error = ValidationError;
+
+    fn try_from(model: &CubeModel) -> Result<Self, Self::Error> {
+        let elements = model.cube_elements();
+        let orientations = model.orientations();
+        // for every slot the piece then its orientation, shifted past the bits of the piece;
+        // the orientations are 0 to 2 for a corner, 0 or 1 for an edge and 0 for a center
+        let pieces = |slots: &[(usize, usize, usize)], piece_bits: usize, range: u8| {
+            let mut found = 0u16;
+            slots
+                .iter()
+                .map(|&(x, y, z)| {
+                    let element = elements[x][y][z];
+                    let orientation = orientations[x][y][z];
+                    let piece = slots.iter().position(|slot| *slot == element.home()).ok_or(
+                        ValidationError::MisplacedElement {
+                            position: (x, y, z),
+                            element,
+                        },
+                    )?;
+                    if found & 1 << piece != 0 {
+                        return Err(ValidationError::DuplicateElement { element });
+                    }
+                    found |= 1 << piece;
+                    if orientation >= range {
+                        return Err(ValidationError::InvalidOrientation {
+                            position: (x, y, z),
+                            orientation,
+                        });
+                    }
+                    Ok(piece as u64 | (orientation as u64) << piece_bits)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        if elements[1][1][1] != CubeElement::Kernel {
+            return Err(ValidationError::MisplacedElement {
+                position: (1, 1, 1),
+                element: elements[1][1][1],
+            });
+        }
+
+        let corner_slots = CORNER_SLOTS.map(|(pos, _)| pos);
+        let edge_slots = EDGE_SLOTS.map(|(pos, _)| pos);
+        let center_slots = CENTER_SLOTS.map(|(pos, _)| pos);
+        let mut packed = PackedCube {
+            corners: 0,
+            edges: 0,
+            centers: 0,
+        };
+        for (slot, piece) in pieces(&corner_slots, 3, 3)?.into_iter().enumerate() {
+            packed.corners |= piece << (slot * CORNER_BITS);
+        }
+        for (slot, piece) in pieces(&edge_slots, 4, 2)?.into_iter().enumerate() {
+            packed.edges |= piece << (slot * EDGE_BITS);
+        }
+        for (slot, piece) in pieces(&center_slots, CENTER_BITS, 1)?.into_iter().enumerate() {
+            packed.centers |= (piece as u32) << (slot * CENTER_BITS);
+        }
+        Ok(packed)
+    }
+}
+
+impl From<PackedCube> for CubeModel {
+    fn from(packed: PackedCube) -> Self {
+        let mut elements = [[[CubeElement::Kernel; 3]; 3]; 3];
+        let mut orientations = [[[0; 3]; 3]; 3];
+        for (slot, ((x, y, z), _)) in CORNER_SLOTS.iter().enumerate() {
+            let (piece, orientation) = packed.corner(slot);
+            elements[*x][*y][*z] = element_at(CORNER_SLOTS[piece as usize].0);
+            orientations[*x][*y][*z] = orientation;
+        }
+        for (slot, ((x, y, z), _)) in EDGE_SLOTS.iter().enumerate() {
+            let (piece, orientation) = packed.edge(slot);
+            elements[*x][*y][*z] = element_at(EDGE_SLOTS[piece as usize].0);
+            orientations[*x][*y][*z] = orientation;
+        }
+        for (slot, ((x, y, z), _)) in CENTER_SLOTS.iter().enumerate() {
+            elements[*x][*y][*z] = element_at(CENTER_SLOTS[packed.center(slot) as usize].0);
+        }
+        CubeModel::new(elements, orientations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrambled() -> CubeModel {
+        let mut model = CubeModel::solved();
+        model.right_clockwise();
+        model.up_clockwise();
+        model.front_counter_clockwise();
+        model.middle_clockwise();
+        model.rotation_y_clockwise();
+        model.back_half_turn();
+        model
+    }
+
+    #[test]
+    fn solved_cube() {
+        let packed = PackedCube::try_from(&CubeModel::solved()).unwrap();
+        assert_eq!(packed, PackedCube::SOLVED);
+        assert!(packed.is_solved());
+        assert_eq!(CubeModel::from(packed), CubeModel::solved());
+        assert_eq!(packed.corner_permutation(), 0);
+        assert_eq!(packed.edge_permutation(), 0);
+    }
+
+    #[test]
+    fn conversion_is_lossless() {
+        let model = scrambled();
+        let packed = PackedCube::try_from(&model).unwrap();
+        assert!(!packed.centers_solved());
+        assert_eq!(CubeModel::from(packed), model);
+    }
+
+    #[test]
+    fn multiply_applies_moves() {
+        let mut model = scrambled();
+        let packed = PackedCube::try_from(&model).unwrap();
+        let mut moves = CubeModel::solved();
+        moves.left_counter_clockwise();
+        moves.standing_clockwise();
+        let moves = PackedCube::try_from(&moves).unwrap();
+
+        model.left_counter_clockwise();
+        model.standing_clockwise();
+        assert_eq!(CubeModel::from(packed.multiply(&moves)), model);
+    }
+
+    #[test]
+    fn inverse_undoes_cube() {
+        let packed = PackedCube::try_from(&scrambled()).unwrap();
+        assert!(packed.multiply(&packed.inverse()).is_solved());
+        assert!(packed.inverse().multiply(&packed).is_solved());
+
+        let mut moves = CubeModel::solved();
+        moves.right_clockwise();
+        moves.up_half_turn();
+        let mut undo = CubeModel::solved();
+        undo.up_half_turn();
+        undo.right_counter_clockwise();
+        let moves = PackedCube::try_from(&moves).unwrap();
+        assert_eq!(moves.inverse(), PackedCube::try_from(&undo).unwrap());
+    }
+
+    #[test]
+    fn coordinates() {
+        let mut model = CubeModel::solved();
+        model.right_clockwise();
+        let packed = PackedCube::try_from(&model).unwrap();
+        // R twists URF by 2, UBR by 1, DFR by 1 and DRB by 2, and flips no edge
+        assert_eq!(packed.corner_orientation(), 2 * 729 + 27 + 9);
+        assert_eq!(packed.edge_orientation(), 0);
+        assert!(packed.corner_permutation() < 40320);
+        assert!(packed.edge_permutation() < 479001600);
+    }
+
+    #[test]
+    fn element_out_of_its_slots() {
+        let mut elements = *CubeModel::solved().cube_elements();
+        elements[1][2][0] = CubeElement::WhiteBlueOrange;
+        let model = CubeModel::new(elements, [[[0; 3]; 3]; 3]);
+        assert_eq!(
+            PackedCube::try_from(&model),
+            Err(ValidationError::MisplacedElement {
+                position: (1, 2, 0),
+                element: CubeElement::WhiteBlueOrange,
+            })
+        );
+    }
+
+    #[test]
+    fn duplicate_element() {
+        let mut elements = *CubeModel::solved().cube_elements();
+        let duplicate = elements[2][2][2];
+        elements[2][2][0] = duplicate;
+        let model = CubeModel::new(elements, [[[0; 3]; 3]; 3]);
+        assert_eq!(
+            PackedCube::try_from(&model),
+            Err(ValidationError::DuplicateElement { element: duplicate })
+        );
+    }
+
+    #[test]
+    fn orientations_out_of_range() {
+        // an edge of 2 or a corner of 3 would spill into the piece of the next slot
+        for (position, orientation) in [((2, 2, 1), 2), ((2, 2, 2), 3), ((1, 2, 1), 1)] {
+            let mut orientations = [[[0; 3]; 3]; 3];
+            orientations[position.0][position.1][position.2] = orientation;
+            let model = CubeModel::new(*CubeModel::solved().cube_elements(), orientations);
+            assert_eq!(
+                PackedCube::try_from(&model),
+                Err(ValidationError::InvalidOrientation { position, orientation })
+            );
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use super::model::{slot_faces, CubeElement, CubeModel, Face, SOLVED_INDEX_MAP};
+
+type Vector = (isize, isize, isize);
+
+/// One of the 48 symmetries of the cube: the 24 rotations and the 24 reflections.
+/// It is a signed permutation of the axes, axis `i` of the image being axis `axes[i]` of the
+/// vector multiplied by `signs[i]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    axes: [usize; 3],
+    signs: [isize; 3],
+}
+
+impl Symmetry {
+    pub const IDENTITY: Symmetry = Symmetry {
+        axes: [0, 1, 2],
+        signs: [1, 1, 1],
+    };
+
+    /// The reflection swapping the Left and Right faces.
+    pub const MIRROR: Symmetry = Symmetry {
+        axes: [0, 1, 2],
+        signs: [-1, 1, 1],
+    };
+
+    /// The 48 symmetries, starting with the identity.
+    pub fn all() -> impl Iterator<Item = Symmetry> {
+        const PERMUTATIONS: [[usize; 3]; 6] = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+        PERMUTATIONS.into_iter().flat_map(|axes| {
+            (0..8).map(move |bits| Symmetry {
+                axes,
+                signs: [0, 1, 2].map(|i| if bits & (1 << i) == 0 { 1 } else { -1 }),
+            })
+        })
+    }
+
+    /// True for the reflections, which turn clockwise turns into counter clockwise ones.
+    pub fn is_mirror(&self) -> bool {
+        let inversions = (0..3)
+            .flat_map(|i| (i + 1..3).map(move |j| (i, j)))
+            .filter(|(i, j)| self.axes[*i] > self.axes[*j])
+            .count();
+        let sign = if inversions % 2 == 0 { 1 } else { -1 };
+        sign * self.signs.iter().product::<isize>() < 0
+    }
+
+    pub fn inverse(&self) -> Symmetry {
+        let mut inverse = Symmetry::IDENTITY;
+        for i in 0..3 {
+            inverse.axes[self.axes[i]] = i;
+            inverse.signs[self.axes[i]] = self.signs[i];
+        }
+        inverse
+    }
+
+    fn apply(&self, vector: Vector) -> Vector {
+        let v = [vector.0, vector.1, vector.2];
+        let [x, y, z] = [0, 1, 2].map(|i| self.signs[i] * v[self.axes[i]]);
+        (x, y, z)
+    }
+
+    /// The face this one is mapped to.
+    pub fn face(&self, face: Face) -> Face {
+        Face::from_normal(self.apply(face.normal()))
+    }
+
+    fn position(&self, (x, y, z): (usize, usize, usize)) -> (usize, usize, usize) {
+        let (x, y, z) = self.apply((x as isize - 1, y as isize - 1, z as isize - 1));
+        ((x + 1) as usize, (y + 1) as usize, (z + 1) as usize)
+    }
+
+    /// The model seen through the symmetry: what the cube would look like if every move that
+    /// led to it had been replaced by its image, e.g. R by L' for `MIRROR`. Centers stay in
+    /// place when they are solved.
+    pub fn conjugate(&self, model: &CubeModel) -> CubeModel {
+        let inverse = self.inverse();
+        let elements = model.cube_elements();
+        let orientations = model.orientations();
+        let mut conjugated_elements = [[[CubeElement::Kernel; 3]; 3]; 3];
+        let mut conjugated_orientations = [[[0; 3]; 3]; 3];
+        for (x, plane) in elements.iter().enumerate() {
+            for (y, row) in plane.iter().enumerate() {
+                for (z, element) in row.iter().enumerate() {
+                    let home = element.home();
+                    let image_home = self.position(home);
+                    let (ix, iy, iz) = self.position((x, y, z));
+                    conjugated_elements[ix][iy][iz] = SOLVED_INDEX_MAP.iter().find(|(pos, _)| *pos == image_home).unwrap().1;
+
+                    let faces = slot_faces((x, y, z));
+                    if faces.len() < 2 {
+                        continue;
+                    }
+                    // the sticker measuring the orientation of the image, found on the element
+                    let home_faces = slot_faces(home);
+                    let reference = inverse.face(slot_faces(image_home)[0]);
+                    let sticker = home_faces.iter().position(|face| *face == reference).unwrap();
+                    // the element only went through rotations, keeping the order of its stickers
+                    let facing = faces[(orientations[x][y][z] as usize + sticker) % faces.len()];
+                    conjugated_orientations[ix][iy][iz] = slot_faces((ix, iy, iz))
+                        .iter()
+                        .position(|face| *face == self.face(facing))
+                        .unwrap() as u8;
+                }
+            }
+        }
+        CubeModel::new(conjugated_elements, conjugated_orientations)
+    }
+}
+
+impl CubeModel {
+    /// The smallest of the 48 conjugates of the model, see `Symmetry::conjugate`, with the
+    /// symmetry giving it. Models equal up to rotations and reflections share it.
+    pub fn canonical(&self) -> (CubeModel, Symmetry) {
+        Symmetry::all()
+            .map(|symmetry| (symmetry.conjugate(self), symmetry))
+            .min_by(|(a, _), (b, _)| a.cmp(b))
+            .unwrap()
+    }
+
+    /// True when the models are equal up to rotations and reflections.
+    pub fn is_symmetric_to(&self, other: &CubeModel) -> bool {
+        self.canonical().0 == other.canonical().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetries() {
+        let symmetries: Vec<_> = Symmetry::all().collect();
+        assert_eq!(symmetries.len(), 48);
+        assert_eq!(symmetries[0], Symmetry::IDENTITY);
+        assert_eq!(symmetries.iter().filter(|s| s.is_mirror()).count(), 24);
+        for symmetry in symmetries {
+            let inverse = symmetry.inverse();
+            for face in [Face::Up, Face::Down, Face::Front, Face::Back, Face::Right, Face::Left] {
+                assert_eq!(inverse.face(symmetry.face(face)), face);
+            }
+        }
+        assert_eq!(Symmetry::MIRROR.face(Face::Right), Face::Left);
+        assert_eq!(Symmetry::MIRROR.face(Face::Up), Face::Up);
+    }
+
+    #[test]
+    fn mirror_of_a_move() {
+        let mut model = CubeModel::solved();
+        model.right_clockwise();
+        let mut mirrored = CubeModel::solved();
+        mirrored.left_counter_clockwise();
+        assert_eq!(Symmetry::MIRROR.conjugate(&model), mirrored);
+    }
+
+    #[test]
+    fn conjugates_keep_the_solved_cube() {
+        for symmetry in Symmetry::all() {
+            assert_eq!(symmetry.conjugate(&CubeModel::solved()), CubeModel::solved());
+        }
+    }
+
+    #[test]
+    fn conjugate_by_inverse_restores_model() {
+        let mut model = CubeModel::solved();
+        model.right_clockwise();
+        model.up_clockwise();
+        model.front_counter_clockwise();
+        model.down_half_turn();
+        for symmetry in Symmetry::all() {
+            let conjugated = symmetry.conjugate(&model);
+            assert!(conjugated.is_solvable());
+            assert_eq!(symmetry.inverse().conjugate(&conjugated), model);
+        }
+    }
+
+    #[test]
+    fn canonical_model() {
+        let mut model = CubeModel::solved();
+        model.right_clockwise();
+        model.up_clockwise();
+        let (canonical, symmetry) = model.canonical();
+        assert_eq!(symmetry.conjugate(&model), canonical);
+
+        let mut rotated = CubeModel::solved();
+        rotated.front_clockwise();
+        rotated.right_clockwise();
+        let mut mirrored = CubeModel::solved();
+        mirrored.left_counter_clockwise();
+        mirrored.up_counter_clockwise();
+        assert!(model.is_symmetric_to(&rotated));
+        assert!(model.is_symmetric_to(&mirrored));
+
+        let mut other = CubeModel::solved();
+        other.right_clockwise();
+        other.left_clockwise();
+        assert!(!model.is_symmetric_to(&other));
+    }
+}
@@ -0,0 +1,294 @@
+use super::model::{slot_faces, CubeElement, CubeModel, Face, CENTER_SLOTS, CORNER_SLOTS, EDGE_SLOTS};
+use std::{error::Error, fmt::Display};
+
+/// Invariants broken by a cube that cannot be reached from the solved one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The element sits in a slot of a different kind, e.g. an edge in a corner slot.
+    MisplacedElement {
+        position: (usize, usize, usize),
+        element: CubeElement,
+    },
+    /// The same element is found in more than one slot.
+    DuplicateElement { element: CubeElement },
+    /// The centers are not a rotation of the solved ones, e.g. two opposite colors are adjacent.
+    InvalidCenters,
+    /// The orientation is not one of the piece's, 0 to 2 for a corner and 0 or 1 for an edge.
+    InvalidOrientation {
+        position: (usize, usize, usize),
+        orientation: u8,
+    },
+    /// The corner orientations do not sum to a multiple of 3, `twist` is the remainder.
+    CornerTwist { twist: u8 },
+    /// The edge orientations do not sum to a multiple of 2.
+    EdgeFlip,
+    /// The permutations of corners, edges and centers do not have a matching parity.
+    PermutationParity,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::MisplacedElement { position, element } => {
+                write!(f, "element {} cannot sit in slot {:?}", element, position)
+            }
+            ValidationError::DuplicateElement { element } => {
+                write!(f, "element {} found more than once", element)
+            }
+            ValidationError::InvalidCenters => f.write_str("centers are not a rotation of the solved cube"),
+            ValidationError::InvalidOrientation { position, orientation } => {
+                write!(f, "orientation {} is out of range in slot {:?}", orientation, position)
+            }
+            ValidationError::CornerTwist { twist } => {
+                write!(f, "corners are twisted by {} thirds of a turn", twist)
+            }
+            ValidationError::EdgeFlip => f.write_str("a single edge is flipped"),
+            ValidationError::PermutationParity => {
+                f.write_str("corner and edge permutations have different parity")
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Returns for every slot the index, among `slots`, of the home of the element it holds.
+fn permutation(
+    model: &CubeModel,
+    slots: &[(usize, usize, usize)],
+) -> Result<Vec<usize>, ValidationError> {
+    let elements = model.cube_elements();
+    let mut permutation = Vec::with_capacity(slots.len());
+    for &(x, y, z) in slots {
+        let element = elements[x][y][z];
+        let home = slots
+            .iter()
+            .position(|slot| *slot == element.home())
+            .ok_or(ValidationError::MisplacedElement {
+                position: (x, y, z),
+                element,
+            })?;
+        if permutation.contains(&home) {
+            return Err(ValidationError::DuplicateElement { element });
+        }
+        permutation.push(home);
+    }
+    Ok(permutation)
+}
+
+/// Returns true if the permutation is odd.
+fn is_odd(permutation: &[usize]) -> bool {
+    let mut visited = vec![false; permutation.len()];
+    let mut odd = false;
+    for start in 0..permutation.len() {
+        let mut i = start;
+        let mut length = 0;
+        while !visited[i] {
+            visited[i] = true;
+            i = permutation[i];
+            length += 1;
+        }
+        // a cycle of even length is an odd permutation
+        if length > 0 && length % 2 == 0 {
+            odd = !odd;
+        }
+    }
+    odd
+}
+
+fn determinant(columns: [(isize, isize, isize); 3]) -> isize {
+    let [(a, b, c), (d, e, f), (g, h, i)] = columns;
+    a * (e * i - f * h) - d * (b * i - c * h) + g * (b * f - c * e)
+}
+
+impl CubeModel {
+    /// Checks that the cube can be reached from the solved one by turning its layers.
+    /// The first broken invariant is returned.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let corner_slots = CORNER_SLOTS.map(|(pos, _)| pos);
+        let edge_slots = EDGE_SLOTS.map(|(pos, _)| pos);
+        let center_slots = CENTER_SLOTS.map(|(pos, _)| pos);
+        let corners = permutation(self, &corner_slots)?;
+        let edges = permutation(self, &edge_slots)?;
+        let centers = permutation(self, &center_slots)?;
+        let elements = self.cube_elements();
+        if elements[1][1][1] != CubeElement::Kernel {
+            return Err(ValidationError::MisplacedElement {
+                position: (1, 1, 1),
+                element: elements[1][1][1],
+            });
+        }
+
+        // the centers must be turned as a rigid body: opposite faces stay opposite and
+        // the handedness of the axes is kept
+        let home_face = |face: Face| {
+            let (x, y, z) = CENTER_SLOTS.iter().find(|(_, faces)| faces[0] == face).unwrap().0;
+            slot_faces(elements[x][y][z].home())[0]
+        };
+        let faces = [Face::Up, Face::Front, Face::Right];
+        if faces.iter().any(|f| home_face(f.opposite()) != home_face(*f).opposite())
+            || determinant([
+                home_face(Face::Right).normal(),
+                home_face(Face::Up).normal(),
+                home_face(Face::Back).normal(),
+            ]) != 1
+        {
+            return Err(ValidationError::InvalidCenters);
+        }
+
+        let orientations = self.orientations();
+        let out_of_range = |slots: &[(usize, usize, usize)], range: u8| {
+            slots
+                .iter()
+                .find(|&&(x, y, z)| orientations[x][y][z] >= range)
+                .map(|&(x, y, z)| ValidationError::InvalidOrientation {
+                    position: (x, y, z),
+                    orientation: orientations[x][y][z],
+                })
+        };
+        if let Some(err) = out_of_range(&corner_slots, 3).or_else(|| out_of_range(&edge_slots, 2)) {
+            return Err(err);
+        }
+        let twist = corner_slots
+            .iter()
+            .map(|&(x, y, z)| orientations[x][y][z])
+            .sum::<u8>()
+            % 3;
+        if twist != 0 {
+            return Err(ValidationError::CornerTwist { twist });
+        }
+        let flip = edge_slots
+            .iter()
+            .map(|&(x, y, z)| orientations[x][y][z])
+            .sum::<u8>()
+            % 2;
+        if flip != 0 {
+            return Err(ValidationError::EdgeFlip);
+        }
+
+        if is_odd(&corners) ^ is_odd(&edges) ^ is_odd(&centers) {
+            return Err(ValidationError::PermutationParity);
+        }
+        Ok(())
+    }
+
+    pub fn is_solvable(&self) -> bool {
+        self.validate().is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrambled() -> CubeModel {
+        let mut model = CubeModel::solved();
+        model.right_clockwise();
+        model.up_clockwise();
+        model.front_counter_clockwise();
+        model.left_clockwise();
+        model.back_clockwise();
+        model.down_counter_clockwise();
+        model
+    }
+
+    fn swap(model: &CubeModel, a: (usize, usize, usize), b: (usize, usize, usize)) -> CubeModel {
+        let mut elements = *model.cube_elements();
+        let orientations = *model.orientations();
+        let temp = elements[a.0][a.1][a.2];
+        elements[a.0][a.1][a.2] = elements[b.0][b.1][b.2];
+        elements[b.0][b.1][b.2] = temp;
+        CubeModel::new(elements, orientations)
+    }
+
+    #[test]
+    fn solved_and_scrambled_are_valid() {
+        assert_eq!(CubeModel::solved().validate(), Ok(()));
+        assert_eq!(scrambled().validate(), Ok(()));
+    }
+
+    #[test]
+    fn twisted_corner() {
+        let model = scrambled();
+        let mut orientations = *model.orientations();
+        orientations[2][2][0] = (orientations[2][2][0] + 1) % 3;
+        let model = CubeModel::new(*model.cube_elements(), orientations);
+        assert_eq!(model.validate(), Err(ValidationError::CornerTwist { twist: 1 }));
+    }
+
+    #[test]
+    fn flipped_edge() {
+        let model = scrambled();
+        let mut orientations = *model.orientations();
+        orientations[1][2][0] = 1 - orientations[1][2][0];
+        let model = CubeModel::new(*model.cube_elements(), orientations);
+        assert_eq!(model.validate(), Err(ValidationError::EdgeFlip));
+    }
+
+    #[test]
+    fn out_of_range_orientations() {
+        let model = CubeModel::solved();
+        let mut orientations = *model.orientations();
+        // sums to a multiple of 3, and would overflow a u8 sum
+        orientations[2][2][0] = 3;
+        let corner = CubeModel::new(*model.cube_elements(), orientations);
+        assert_eq!(
+            corner.validate(),
+            Err(ValidationError::InvalidOrientation { position: (2, 2, 0), orientation: 3 })
+        );
+        orientations[2][2][0] = 255;
+        orientations[0][2][0] = 255;
+        assert!(matches!(
+            CubeModel::new(*model.cube_elements(), orientations).validate(),
+            Err(ValidationError::InvalidOrientation { .. })
+        ));
+
+        let mut orientations = *model.orientations();
+        orientations[1][2][0] = 2;
+        let edge = CubeModel::new(*model.cube_elements(), orientations);
+        assert_eq!(
+            edge.validate(),
+            Err(ValidationError::InvalidOrientation { position: (1, 2, 0), orientation: 2 })
+        );
+        assert!(!edge.is_solvable());
+    }
+
+    #[test]
+    fn swapped_edges() {
+        let model = swap(&CubeModel::solved(), (1, 2, 0), (2, 2, 1));
+        assert_eq!(model.validate(), Err(ValidationError::PermutationParity));
+    }
+
+    #[test]
+    fn swapped_edges_and_corners() {
+        let model = swap(&CubeModel::solved(), (1, 2, 0), (2, 2, 1));
+        let model = swap(&model, (2, 2, 0), (2, 2, 2));
+        assert_eq!(model.validate(), Ok(()));
+    }
+
+    #[test]
+    fn swapped_opposite_centers() {
+        let model = swap(&CubeModel::solved(), (1, 2, 1), (1, 0, 1));
+        assert_eq!(model.validate(), Err(ValidationError::InvalidCenters));
+    }
+
+    #[test]
+    fn mirrored_centers() {
+        let model = swap(&CubeModel::solved(), (1, 2, 1), (1, 0, 1));
+        let model = swap(&model, (1, 1, 0), (1, 1, 2));
+        let model = swap(&model, (2, 1, 1), (0, 1, 1));
+        assert_eq!(model.validate(), Err(ValidationError::InvalidCenters));
+    }
+
+    #[test]
+    fn edge_in_corner_slot() {
+        let model = swap(&CubeModel::solved(), (1, 2, 0), (2, 2, 0));
+        assert_eq!(
+            model.validate(),
+            Err(ValidationError::MisplacedElement {
+                position: (2, 2, 0),
+                element: CubeElement::WhiteBlue,
+            })
+        );
+    }
+}
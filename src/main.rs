@@ -1,35 +1,17 @@
-mod agents;
-mod core;
-
-use crate::agents::{Actions, Actionable};
-use crate::agents::{CubeState, StateSpace};
-use crate::core::{CubeModel, CubeTextLoader};
-use enum_iterator::all;
 use rand::prelude::*;
-use std::path::PathBuf;
+use rubik_solver::agents::{Actions, Actionable, Algorithm};
+use rubik_solver::agents::{CubeState, HeuristicRegistry, SearchLimits, SearchResult, StateSpace};
+use std::time::Duration;
+use rubik_solver::core::CubeModel;
 
 fn make_a_100_random_moves_model() -> CubeModel {
     let mut cube = CubeModel::solved();
     for _ in 0..10 {
-        let random_move = all::<Actions>()
-            .collect::<Vec<Actions>>()
+        let random_move = Actions::FACE_TURNS
             .choose(&mut rand::thread_rng())
             .unwrap()
             .to_owned();
-        match random_move {
-            Actions::U => cube.up_clockwise(),
-            Actions::UC => cube.up_counter_clockwise(),
-            Actions::D => cube.down_clockwise(),
-            Actions::DC => cube.down_counter_clockwise(),
-            Actions::F => cube.front_clockwise(),
-            Actions::FC => cube.front_counter_clockwise(),
-            Actions::B => cube.back_clockwise(),
-            Actions::BC => cube.back_counter_clockwise(),
-            Actions::R => cube.right_clockwise(),
-            Actions::RC => cube.right_counter_clockwise(),
-            Actions::L => cube.left_clockwise(),
-            Actions::LC => cube.left_counter_clockwise(),
-        }
+        cube.apply_action(random_move);
     }
     cube
 }
@@ -38,19 +20,39 @@ fn main() {
     // let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
     //     .join("assets")
     //     .join("unsolved_cube.txt");
-    // let loader = CubeTextLoader::from_file(path.as_path());
+    // let loader = CubeTextLoader::from_file(path.as_path()).unwrap();
+    let registry = HeuristicRegistry::default();
+    let name = std::env::args().nth(1).unwrap_or(String::from("misplaced-cubies"));
+    let Some(heuristic) = registry.get(&name) else {
+        let names: Vec<_> = registry.names().collect();
+        eprintln!("Unknown heuristic {}, expected one of: {}", name, names.join(", "));
+        std::process::exit(1);
+    };
     let mut model = make_a_100_random_moves_model();
     let mut state_space = StateSpace::new(
-        CubeState::from_model(model.to_owned()),
-        CubeState::from_model(CubeModel::solved()),
-    );
-    let actions = state_space.solve();
-    println!("Solved in {} steps", actions.as_ref().unwrap().len());
+        CubeState::from_model(model.to_owned()).unwrap(),
+        CubeState::from_model(CubeModel::solved()).unwrap(),
+        heuristic,
+    )
+    .with_limits(SearchLimits::unlimited().with_max_time(Duration::from_secs(60)));
+    let (result, stats) = state_space.solve();
+    println!("{}", stats);
+    let actions = match result {
+        SearchResult::Solved(actions) => actions,
+        SearchResult::LimitReached { limit, best_path } => {
+            println!("Stopped by the {:?} limit, closest state after: {}", limit, Algorithm::from(best_path));
+            return;
+        }
+        SearchResult::Unsolvable => {
+            println!("The cube cannot be solved");
+            return;
+        }
+    };
+    println!("Solved in {} steps", actions.len());
     println!("from:\n{}", model);
-    for action in actions.unwrap() {
-        println!("{:?}", action);
-        model.apply_action(action);
-    }
+    let algorithm = Algorithm::from(actions);
+    println!("{}", algorithm);
+    algorithm.apply_to(&mut model);
     println!("\nto:\n{}", model);
     println!("is solved: {}", model.is_solved());
 }